@@ -151,23 +151,9 @@ impl SysBackend for WebBackend {
         Ok(())
     }
     fn stream_audio(&self, mut f: uiua::AudioStreamFn) -> Result<(), String> {
-        let mut samples = Vec::new();
-        let mut t = 0.0;
         const SAMPLE_RATE: u32 = 44100;
-        const SAMPLES_PER_FRAME: usize = 10000;
-        let mut times = Vec::with_capacity(SAMPLES_PER_FRAME);
-        let ast_time = get_ast_time();
-        while t <= ast_time {
-            times.clear();
-            for _ in 0..SAMPLES_PER_FRAME {
-                times.push(t);
-                t += 1.0 / SAMPLE_RATE as f64;
-            }
-            match f(&times) {
-                Ok(s) => samples.extend(s),
-                Err(err) => return Err(format!("{err}")),
-            }
-        }
+        let samples = uiua::render_audio_stream(&mut f, SAMPLE_RATE, get_ast_time())
+            .map_err(|err| format!("{err}"))?;
         let bytes = uiua::stereo_to_wave_bytes(
             &samples,
             |s| (s * i16::MAX as f64) as i16,
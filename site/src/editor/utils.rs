@@ -788,7 +788,8 @@ fn run_code_single(code: &str) -> Vec<OutputItem> {
     if get_top_at_top() {
         values.reverse();
     }
-    let diagnostics = comp.take_diagnostics();
+    let mut diagnostics = comp.take_diagnostics();
+    diagnostics.extend(rt.take_diagnostics());
     let io = rt.downcast_backend::<WebBackend>().unwrap();
     // Get stdout and stderr
     let stdout = take(&mut *io.stdout.lock().unwrap());
@@ -1009,6 +1010,11 @@ pub fn progressive_strings(input: &str) -> Vec<String> {
                 lines.push(vec![binding.span().as_str(&inputs, |s| s.into())])
             }
             Item::TestScope(items) => lines.push(vec![items.span.as_str(&inputs, |s| s.into())]),
+            Item::Module(module) => lines.push(vec![module.span.as_str(&inputs, |s| s.into())]),
+            Item::Record(record) => lines.push(vec![record.span.as_str(&inputs, |s| s.into())]),
+            Item::Variants(variants) => {
+                lines.push(vec![variants.span.as_str(&inputs, |s| s.into())])
+            }
         }
     }
     let mut strings = Vec::new();
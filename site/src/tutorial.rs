@@ -781,6 +781,19 @@ F 10 11"/>
         <Editor example="$\"_, _, and _\" 1 2 3"/>
         <p>"If you need to use a literal "<code>"_"</code>", you can escape them with "<code>"\\"</code>"."</p>
         <Editor example="$\"\\__\\_\" 27"/>
+        <p>"A "<code>"_"</code>" can be followed by a "<em>"format specifier"</em>" in braces after a "<code>":"</code>", like "<code>"_{:.2}"</code>", to control how the value is formatted. The syntax is "<code>"[[fill]align][+][#][0][width][,][.precision][type]"</code>"."</p>
+        <p>"A "<code>"."</code>" followed by a number sets the number of digits after the decimal point."</p>
+        <Editor example="$\"_ is about _{:.2}\" π π"/>
+        <p>"A number sets the minimum width. Numbers are aligned to the right and other values to the left by default. "<code>"<"</code>", "<code>"^"</code>", and "<code>">"</code>" align to the left, center, and right. A fill character can be put before the alignment."</p>
+        <Editor example="$\"[_{:8.2}] [_{:<8}] [_{:*^8}]\" 3.14159 \"left\" \"mid\""/>
+        <p>"A "<code>"0"</code>" before the width pads numbers with zeros, and a "<code>"+"</code>" always shows the sign."</p>
+        <Editor example="$\"_{:04} _{:+}\" 7 5"/>
+        <p>"The type can be "<code>"x"</code>" or "<code>"X"</code>" for hexadecimal, "<code>"b"</code>" for binary, "<code>"o"</code>" for octal, or "<code>"e"</code>" or "<code>"E"</code>" for scientific notation. A "<code>"#"</code>" adds a "<code>"0x"</code>", "<code>"0b"</code>", or "<code>"0o"</code>" prefix."</p>
+        <Editor example="$\"_{:x} _{:#X} _{:#b} _{:.2e}\" 255 255 10 1234.5"/>
+        <p>"A "<code>","</code>" separates groups of digits."</p>
+        <Editor example="$\"_{:,.2}\" 1234567.891"/>
+        <p>"If the text in the braces is not a valid specifier, it is left as it is."</p>
+        <Editor example="$\"_{:abc} _:2\" 1 2"/>
         <p>"Multi-line strings are implicitly format strings."</p>
         <Editor example="+,, 1 2\n&p $ What are two numbers that add up to _?\n   $ _ and _ do!"/>
 
@@ -797,6 +810,15 @@ F 10 11"/>
         <Editor example="≡(|2 ↻.) 1_2_3 ↯3_3⇡9"/> // Should fail
         <p>"If the compiler cannot derive the stack signature of a function and you give it one which is "<em>"wrong"</em>", the function will throw an error at runtime."</p>
 
+        <h2 id="annotations">"Type and Shape Annotations"</h2>
+        <p>"A binding with a stack signature can also annotate the types and shapes of its arguments. The annotations go after the signature and are ended with another "<code>"|"</code>". The first annotation is for the argument on top of the stack."</p>
+        <p>"An annotation can be a type, one of "<code>"num"</code>", "<code>"byte"</code>", "<code>"complex"</code>", "<code>"char"</code>", or "<code>"box"</code>", followed by an optional shape in "<code>"[]"</code>"s. Dimensions in a shape can be numbers, names, "<code>"_"</code>" for any length, or "<code>".."</code>" for any number of dimensions. A name must have the same length everywhere it appears."</p>
+        <Editor example="MatMul ← |2 num[n m] num[m k] | ⊞(/+×)⊙⍉
+MatMul [1_2 3_4] [5_6 7_8]"/>
+        <p>"If a call's arguments do not match the annotations, a warning is shown. Calls are checked when the program is compiled if the arguments can be determined, and when it is run otherwise. A function passed to a modifier is checked every time the modifier calls it."</p>
+        <Editor example="MatMul ← |2 num[n m] num[m k] | ⊞(/+×)⊙⍉
+MatMul [1_2_3] [1_2]"/>
+
         <h2 id="proxy">"Proxy Values"</h2>
         <p>"Because Uiua is a dynamically typed language, the types and shapes of values returned by functions are not known until runtime. This creates a problem when iterating over arrays. What if the array is empty? The function will never be run, so the proper type and shape of the result cannot be determined. Uiua has a way to get around this "<a href="https://mlochbaum.github.io/BQN/commentary/problems.html#empty-arrays-lose-type-information">"problem"</a>" that some other array languages have."</p>
         <p>"Some iterating modifiers use what are called "<em>"proxy values"</em>" if the iterated array is empty. The modifier's function is called on these values if the array is empty. This ensures that the result has the correct shape and type, even if the function is never run on the actual values."</p>
@@ -1030,6 +1052,14 @@ F
 °F
 ⍜F"G""#/>
 
+        <h2 id="records">"Records"</h2>
+        <p>"A record type is declared with a name, a "<code>"~"</code>", and the names of its fields. This creates several bindings."</p>
+        <p>"The record's name is bound to a constructor function which takes a value for each field and puts them in a list of boxes. Each field's name is bound to a function which gets that field from a record. "<code>"Set"</code>" followed by a field's name is bound to a function which sets that field."</p>
+        <Editor example="Person ~ Name Age\nPerson \"Alice\" 30\nName .\nSetAge 31 :"/>
+        <p>"Field functions work with "<Prim prim=Under/>", so a field can be updated in place."</p>
+        <Editor example="Person ~ Name Age\n⍜Age(+1) Person \"Alice\" 30"/>
+        <p>"It is an error for a field function to replace a binding that already exists in the same scope. To have two records with the same field name, declare one of them in a scoped module."</p>
+
         <h2 id="challenges">"Challenges"</h2>
 
         <Challenge
@@ -1117,6 +1147,15 @@ splitArray([1, 2, 3, 7, 2, 4, 5])"</code>
         <p>"Because a second "<code>"|"</code>" immediately after another indicates a signature, branches that do nothing must contain "<Prim prim=Identity/>"."</p>
         <Editor example="F ← (+5|∘|÷10)+∩>5,10.\n[F2 F6 F200]"/>
 
+        <h2 id="variants">"Variant Types"</h2>
+        <p>"A "<em>"variant type"</em>" is a value that can be one of several named variants, each of which carries its own fields. It is declared like a "<A href="/docs/inverses#records">"record"</A>", but with the variants separated by "<code>"|"</code>"s. The first name in each variant is the variant's name, and the rest are its fields."</p>
+        <p>"Each variant's name is bound to a constructor function. A variant value is a list of boxes that starts with the variant's tag."</p>
+        <Editor example="Shape ~ Circle R | Rect W H | Empty\nCircle 5\nRect 2 3\nEmpty"/>
+        <p>"The variant type's name followed by a "<code>"!"</code>" is a modifier that matches on the variants. It takes a switch function with a branch for each variant. The branch for the value's variant is called with the variant's fields on the stack."</p>
+        <Editor example="Shape ~ Circle R | Rect W H | Empty\nArea ← Shape!(×π×.|×|0)\nArea Circle 1\nArea Rect 2 3\nArea Empty"/>
+        <p>"Every variant must have a branch. A missing branch is an error."</p>
+        <Editor example="Shape ~ Circle R | Rect W H | Empty\nArea ← Shape!(×π×.|×)"/> // Should fail
+
         <h2 id="assert"><Prim prim=Assert/></h2>
         <p>"The "<Prim prim=Assert/>" function takes any value and a condition. If the condition is anything but "<code>"1"</code>", the value is thrown as an error that can be caught with "<Prim prim=Try/>"."</p>
         <Editor example="F ← ⍣(¯⍤10≤10.)◌\nF 5\nF 12"/>
@@ -1434,6 +1473,38 @@ Sqr ← Ex \"Square\"
 Inc Sqr Dub 5"/>
         <p>"When you write code like this that imports several items, the formatter will automatically indent each item. Try it out!"</p>
 
+        <h2 id="standard-library">"Standard Library"</h2>
+        <p>"Some modules are bundled with Uiua. Their paths start with "<code>"std/"</code>"."</p>
+        <Editor example="\
+Str ← &i \"std/strings\"
+Str \"Split\" \", \" \"a, b, c\"
+Str \"PadLeft\" 6 \"hi\""/>
+        <p>"The available modules are "<code>"std/strings"</code>" and "<code>"std/matrix"</code>". Each item is documented with a comment above its binding."</p>
+
+        <h2 id="inline-modules">"Inline Modules"</h2>
+        <p>"Modules can also be declared inside a file. An inline module is delimited with "<code>"---"</code>"s like a test scope, but the first "<code>"---"</code>" is followed by the module's name."</p>
+        <p>"Items from an inline module are accessed the same way as items from an imported module."</p>
+        <Editor example="\
+---Shapes
+Area ← ×.
+Cube ← ×Area.
+---
+Cube ← Shapes \"Cube\"
+Cube 3
+Shapes \"Area\" 4"/>
+        <p>"Bindings inside an inline module can see bindings from before the module, but bindings after the module can only reach its items through its name."</p>
+        <h2 id="private-bindings">"Private Bindings"</h2>
+        <p>"Bindings made with "<code>"↚"</code>" instead of "<code>"←"</code>" are private. They can be used anywhere within their module, but they cannot be accessed from outside of it."</p>
+        <p><code>"↚"</code>" can be typed as "<code>"=~"</code>". The formatter will turn it into the glyph."</p>
+        <Editor example="\
+---Shapes
+Sq ↚ ×.
+Area ← Sq
+Cube ← ×Sq.
+---
+Shapes \"Cube\" 3
+Shapes \"Sq\" 4"/> // Should fail
+        <p>"This lets a module hide helper functions and export only the items that are meant to be used. The same applies to modules imported with "<Prim prim=Sys(SysOp::Import)/>"."</p>
     }
 }
 
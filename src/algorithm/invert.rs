@@ -28,6 +28,7 @@ fn prim_inverse(prim: Primitive, span: usize) -> Option<Instr> {
         Box => Instr::ImplPrim(InvBox, span),
         Where => Instr::ImplPrim(InvWhere, span),
         Utf => Instr::ImplPrim(InvUtf, span),
+        Graphemes => Instr::ImplPrim(InvGraphemes, span),
         Parse => Instr::ImplPrim(InvParse, span),
         Fix => Instr::ImplPrim(InvFix, span),
         Map => Instr::ImplPrim(InvMap, span),
@@ -48,6 +49,7 @@ fn impl_prim_inverse(prim: ImplPrimitive, span: usize) -> Option<Instr> {
         InverseBits => Instr::Prim(Bits, span),
         InvWhere => Instr::Prim(Where, span),
         InvUtf => Instr::Prim(Utf, span),
+        InvGraphemes => Instr::Prim(Graphemes, span),
         InvAtan => Instr::Prim(Atan, span),
         InvComplex => Instr::Prim(Complex, span),
         InvCouple => Instr::Prim(Couple, span),
@@ -258,6 +260,9 @@ pub(crate) fn under_instrs(
         &pat!(Sign, (Dup, Abs, PushTempN(1), Sign), (PopTempN(1), Mul)),
         &pat!(First, (CopyToTempN(1), First), (PopTempN(1), Unfirst)),
         &pat!(Last, (CopyToTempN(1), Last), (PopTempN(1), Unlast)),
+        &pat!(Upper, (CopyToTempN(1), Upper), (PopTempN(1), RestoreUpper)),
+        &pat!(Lower, (CopyToTempN(1), Lower), (PopTempN(1), RestoreLower)),
+        &pat!(Title, (CopyToTempN(1), Title), (PopTempN(1), RestoreTitle)),
         &pat!(Shape, (CopyToTempN(1), Shape), (PopTempN(1), Flip, Reshape)),
         &pat!(
             Deshape,
@@ -428,7 +433,9 @@ fn invert_trivial_pattern<'a>(
                 return Some((input, inv));
             }
         }
-        [Comment(_) | PushSig(_) | PopSig, input @ ..] => return Some((input, EcoVec::new())),
+        [Comment(_) | PushSig(_) | PopSig | CheckAnnotation { .. }, input @ ..] => {
+            return Some((input, EcoVec::new()))
+        }
         _ => {}
     }
     None
@@ -445,6 +452,9 @@ fn under_trivial_pattern<'a>(
             Some((input, (eco_vec![instr.clone()], eco_vec![])))
         }
         [Comment(_) | PushSig(_) | PopSig, input @ ..] => Some((input, (eco_vec![], eco_vec![]))),
+        [instr @ CheckAnnotation { .. }, input @ ..] => {
+            Some((input, (eco_vec![instr.clone()], eco_vec![])))
+        }
         _ => None,
     }
 }
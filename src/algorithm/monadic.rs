@@ -5,19 +5,22 @@ use std::{
     collections::{HashMap, HashSet},
     f64::consts::{PI, TAU},
     iter::repeat,
-    mem::size_of,
+    mem::{size_of, take},
     ptr,
 };
 
 use ecow::{eco_vec, EcoVec};
 use rayon::prelude::*;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{
     array::*,
     cowslice::{cowslice, CowSlice},
     grid_fmt::GridFmt,
     value::Value,
-    Boxed, Primitive, Shape, Uiua, UiuaResult,
+    Boxed, Complex, Primitive, Shape, Uiua, UiuaResult,
 };
 
 use super::{op_bytes_retry_fill, ArrayCmpSlice, FillContext};
@@ -63,50 +66,90 @@ impl Value {
     }
     /// Attempt to parse the value into a number
     pub fn parse_num(&self, env: &Uiua) -> UiuaResult<Self> {
+        self.parse_num_in(None, env)
+    }
+    /// Convert numbers to strings or strings to numbers in a base
+    pub fn radix(&self, target: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let base = self.as_nat(env, "Base must be a natural number")?;
+        if !(2..=36).contains(&base) {
+            return Err(env.error(format!("Base must be between 2 and 36, but it is {base}")));
+        }
+        let base = base as u32;
+        match target {
+            Value::Char(_) | Value::Box(_) => target.parse_num_in(Some(base), env),
+            _ => target.format_in_base(base, env),
+        }
+    }
+    fn parse_num_in(&self, base: Option<u32>, env: &Uiua) -> UiuaResult<Self> {
         Ok(match (self, self.shape().dims()) {
             (Value::Char(arr), [] | [_]) => {
-                let mut s: String = arr.data.iter().copied().collect();
-                if s.contains('¯') {
-                    s = s.replace('¯', "-");
-                }
-                if s.contains('`') {
-                    s = s.replace('`', "-");
-                }
-                if s.contains('η') {
-                    s = s.replace('η', &(PI * 0.5).to_string());
-                }
-                if s.contains('π') {
-                    s = s.replace('π', &PI.to_string());
-                }
-                if s.contains('τ') {
-                    s = s.replace('τ', &TAU.to_string());
-                }
-                if s.contains('∞') {
-                    s = s.replace('∞', &f64::INFINITY.to_string());
-                }
-                match s.split_once('/') {
-                    Some((numer, denom)) => numer
-                        .parse::<f64>()
-                        .and_then(|n| denom.parse::<f64>().map(|d| n / d)),
-                    None => s.parse::<f64>(),
+                let s: String = arr.data.iter().copied().collect();
+                let parsed = match base {
+                    Some(base) => parse_int_in_base(&s, base).map(ParsedNum::Real),
+                    None => parse_number(&s),
+                };
+                match parsed {
+                    Some(ParsedNum::Real(n)) => n.into(),
+                    Some(ParsedNum::Complex(c)) => c.into(),
+                    None => {
+                        return Err(env.error(match base {
+                            Some(base) => {
+                                format!("Cannot parse {s:?} into a base {base} integer")
+                            }
+                            None => format!(
+                                "Cannot parse {s:?} into a number. Accepted forms are \
+                                decimals (1.5, ¯2), scientific notation (1.5e3, 1E¯3), \
+                                fractions (1/2), hexadecimal (0xff), binary (0b1010), \
+                                octal (0o17), thousands separators (1,234.5), constants \
+                                (π, τ, η, ∞), and complex numbers (3+4i, ¯2i)"
+                            ),
+                        }))
+                    }
                 }
-                .map_err(|e| env.error(format!("Cannot parse into number: {}", e)))?
-                .into()
             }
             (Value::Box(arr), []) => {
                 let value = &arr.data[0].0;
-                value.parse_num(env)?
+                value.parse_num_in(base, env)?
             }
             (Value::Char(_) | Value::Box(_), _) => {
                 let mut rows = Vec::with_capacity(self.row_count());
                 for row in self.rows() {
-                    rows.push(row.parse_num(env)?);
+                    rows.push(row.parse_num_in(base, env)?);
                 }
                 Value::from_row_values(rows, env)?
             }
             (val, _) => return Err(env.error(format!("Cannot parse {} array", val.type_name()))),
         })
     }
+    fn format_in_base(&self, base: u32, env: &Uiua) -> UiuaResult<Self> {
+        let format = |n: f64| {
+            format_in_base(n, base).ok_or_else(|| {
+                env.error(format!(
+                    "Cannot format {} in base {base} because it is not an integer",
+                    n.grid_string(false)
+                ))
+            })
+        };
+        let nums: Vec<f64> = match self {
+            Value::Num(nums) => nums.data.to_vec(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(bytes) => bytes.data.iter().map(|&b| b as f64).collect(),
+            val => {
+                return Err(env.error(format!(
+                    "Cannot format {} in base {base}",
+                    val.type_name_plural()
+                )))
+            }
+        };
+        if self.rank() == 0 {
+            return Ok(format(nums[0])?.into());
+        }
+        let mut new_data = EcoVec::with_capacity(nums.len());
+        for n in nums {
+            new_data.push(Boxed(format(n)?.into()));
+        }
+        Ok(Array::new(self.shape().clone(), new_data).into())
+    }
     pub(crate) fn inv_parse(&self, env: &Uiua) -> UiuaResult<Self> {
         if self.rank() == 0 {
             return match self {
@@ -143,6 +186,113 @@ impl Value {
     }
 }
 
+enum ParsedNum {
+    Real(f64),
+    Complex(Complex),
+}
+
+/// Parse a number in any of the forms accepted by [`Primitive::Parse`]
+fn parse_number(s: &str) -> Option<ParsedNum> {
+    let s = s.trim();
+    if let Some(n) = parse_real(s) {
+        return Some(ParsedNum::Real(n));
+    }
+    // Complex numbers
+    let s = s.strip_suffix('i')?;
+    let is_sign = |c: char| "+-¯`".contains(c);
+    let split = s
+        .char_indices()
+        .rev()
+        .find(|&(i, c)| i > 0 && is_sign(c) && !s[..i].ends_with(['e', 'E']));
+    let (re, im) = match split {
+        Some((i, _)) => (&s[..i], &s[i..]),
+        None => ("0", s),
+    };
+    let im = match im {
+        "" | "+" => 1.0,
+        "-" | "¯" | "`" => -1.0,
+        im => parse_real(im.strip_prefix('+').unwrap_or(im))?,
+    };
+    Some(ParsedNum::Complex(Complex::new(parse_real(re)?, im)))
+}
+
+/// Parse a real number
+fn parse_real(s: &str) -> Option<f64> {
+    if let Some((numer, denom)) = s.split_once('/') {
+        return Some(parse_real(numer)? / parse_real(denom)?);
+    }
+    let (neg, s) = match s.strip_prefix(['-', '¯', '`']) {
+        Some(s) => (true, s),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    if s.starts_with(['-', '¯', '`', '+']) {
+        return None;
+    }
+    let radix = match s.get(..2) {
+        Some("0x" | "0X") => Some(16),
+        Some("0b" | "0B") => Some(2),
+        Some("0o" | "0O") => Some(8),
+        _ => None,
+    };
+    let n = if let Some(radix) = radix {
+        u128::from_str_radix(&s[2..], radix).ok()? as f64
+    } else {
+        match s {
+            "η" => PI / 2.0,
+            "π" => PI,
+            "τ" => TAU,
+            "∞" => f64::INFINITY,
+            s if s.contains(',') => {
+                let int_len = s.find(['.', 'e', 'E']).unwrap_or(s.len());
+                let (int, rest) = s.split_at(int_len);
+                let mut groups = int.split(',');
+                let first = groups.next()?;
+                let valid = (1..=3).contains(&first.len())
+                    && groups.all(|g| g.len() == 3)
+                    && !rest.contains(',');
+                if !valid {
+                    return None;
+                }
+                s.replace(',', "").replace(['¯', '`'], "-").parse().ok()?
+            }
+            s => s.replace(['¯', '`'], "-").parse().ok()?,
+        }
+    };
+    Some(if neg { -n } else { n })
+}
+
+/// Parse an integer in the given base
+fn parse_int_in_base(s: &str, base: u32) -> Option<f64> {
+    let s = s.trim();
+    let (neg, s) = match s.strip_prefix(['-', '¯', '`']) {
+        Some(s) => (true, s),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let n = u128::from_str_radix(s, base).ok()? as f64;
+    Some(if neg { -n } else { n })
+}
+
+/// Format an integer in the given base
+fn format_in_base(n: f64, base: u32) -> Option<String> {
+    if n.fract() != 0.0 || !n.is_finite() {
+        return None;
+    }
+    let mut abs = n.abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = (abs % base as f64) as u32;
+        digits.push(std::char::from_digit(digit, base)?);
+        abs = (abs / base as f64).floor();
+        if abs == 0.0 {
+            break;
+        }
+    }
+    if n < 0.0 {
+        digits.push('¯');
+    }
+    Some(digits.into_iter().rev().collect())
+}
+
 impl<T: ArrayValue> Array<T> {
     /// Make the array 1-dimensional
     pub fn deshape(&mut self) {
@@ -996,6 +1146,273 @@ impl Value {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Upper,
+    Lower,
+    Title,
+}
+
+impl Case {
+    fn name(&self) -> &'static str {
+        match self {
+            Case::Upper => "upper",
+            Case::Lower => "lower",
+            Case::Title => "title",
+        }
+    }
+    /// Convert a string to this case
+    ///
+    /// If `same_len` is set, characters whose conversion is not a single
+    /// character are left as they are.
+    fn convert(self, s: &str, same_len: bool) -> String {
+        fn push_converted(c: char, upper: bool, same_len: bool, out: &mut String) {
+            let start = out.len();
+            if upper {
+                out.extend(c.to_uppercase());
+            } else {
+                out.extend(c.to_lowercase());
+            }
+            if same_len && out[start..].chars().nth(1).is_some() {
+                out.truncate(start);
+                out.push(c);
+            }
+        }
+        match self {
+            Case::Upper if !same_len => s.to_uppercase(),
+            Case::Lower if !same_len => s.to_lowercase(),
+            Case::Upper | Case::Lower => {
+                let mut out = String::with_capacity(s.len());
+                for c in s.chars() {
+                    push_converted(c, self == Case::Upper, true, &mut out);
+                }
+                out
+            }
+            Case::Title => {
+                let mut out = String::with_capacity(s.len());
+                for word in s.split_word_bounds() {
+                    let mut chars = word.chars();
+                    let Some(first) = chars.next() else {
+                        continue;
+                    };
+                    // Digraphs have dedicated titlecase forms
+                    match first {
+                        'Ǆ' | 'ǅ' | 'ǆ' => out.push('ǅ'),
+                        'Ǉ' | 'ǈ' | 'ǉ' => out.push('ǈ'),
+                        'Ǌ' | 'ǋ' | 'ǌ' => out.push('ǋ'),
+                        'Ǳ' | 'ǲ' | 'ǳ' => out.push('ǲ'),
+                        c => push_converted(c, true, same_len, &mut out),
+                    }
+                    for c in chars {
+                        push_converted(c, false, same_len, &mut out);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Split a string into a list of boxed grapheme clusters
+    pub fn graphemes(&self, env: &Uiua) -> UiuaResult<Self> {
+        let s = self.as_string(env, "Argument to graphemes must be a string")?;
+        Ok(Array::<Boxed>::from_iter(s.graphemes(true).map(String::from)).into())
+    }
+    /// Join a list of boxed grapheme clusters into a string
+    pub fn inv_graphemes(&self, env: &Uiua) -> UiuaResult<Self> {
+        let requirement = "Argument to inverse graphemes must be a list of boxed strings";
+        match self {
+            Value::Char(chars) if chars.rank() <= 1 => Ok(self.clone()),
+            Value::Box(boxes) if boxes.rank() <= 1 => {
+                let mut s = String::new();
+                for Boxed(val) in &boxes.data {
+                    s.push_str(&val.as_string(env, requirement)?);
+                }
+                Ok(s.into())
+            }
+            val if val.rank() == 1 && val.row_count() == 0 => Ok(String::new().into()),
+            val => Err(env.error(format!(
+                "{requirement}, but it is a rank {} {} array",
+                val.rank(),
+                val.type_name()
+            ))),
+        }
+    }
+    /// Convert a string to uppercase
+    pub fn upper(self, env: &Uiua) -> UiuaResult<Self> {
+        self.change_case(Case::Upper, env)
+    }
+    /// Convert a string to lowercase
+    pub fn lower(self, env: &Uiua) -> UiuaResult<Self> {
+        self.change_case(Case::Lower, env)
+    }
+    /// Convert a string to title case
+    pub fn title(self, env: &Uiua) -> UiuaResult<Self> {
+        self.change_case(Case::Title, env)
+    }
+    fn change_case(self, case: Case, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            Value::Char(chars) if chars.rank() <= 1 => {
+                let s: String = chars.data.iter().collect();
+                case.convert(&s, false).into()
+            }
+            Value::Char(mut chars) => {
+                let row_len = chars.shape.last().copied().unwrap_or(1).max(1);
+                for row in chars.data.as_mut_slice().chunks_exact_mut(row_len) {
+                    let s: String = row.iter().collect();
+                    for (c, converted) in row.iter_mut().zip(case.convert(&s, true).chars()) {
+                        *c = converted;
+                    }
+                }
+                chars.into()
+            }
+            Value::Box(mut boxes) => {
+                for Boxed(val) in boxes.data.as_mut_slice() {
+                    *val = take(val).change_case(case, env)?;
+                }
+                boxes.into()
+            }
+            val => {
+                return Err(env.error(format!(
+                    "Argument to {} must be a string, but it is {}",
+                    case.name(),
+                    val.type_name_plural()
+                )))
+            }
+        })
+    }
+    /// Restore the case of characters in `self` that only differ from `orig` by case
+    ///
+    /// This is used to implement [`Primitive::Under`] for case conversions.
+    pub(crate) fn restore_case(self, orig: &Self) -> Self {
+        match (self, orig) {
+            (Value::Char(chars), Value::Char(orig)) if chars.rank() <= 1 && orig.rank() <= 1 => {
+                // Case conversion can change the length of a string, as with `ß` → `SS`,
+                // so each original character is matched against its case conversions.
+                // The function may have added or removed characters, so the common
+                // prefix and suffix are restored and the characters between are kept.
+                let conversions = |o: char| {
+                    [
+                        o.to_uppercase().collect::<Vec<_>>(),
+                        o.to_lowercase().collect(),
+                    ]
+                };
+                let (mut new, mut orig) = (&chars.data[..], &orig.data[..]);
+                let mut restored = String::with_capacity(new.len());
+                while let Some((&o, rest)) = orig.split_first() {
+                    let Some(conv) =
+                        (conversions(o).into_iter()).find(|conv| new.starts_with(conv))
+                    else {
+                        break;
+                    };
+                    restored.push(o);
+                    new = &new[conv.len()..];
+                    orig = rest;
+                }
+                let mut suffix = Vec::new();
+                while let Some((&o, rest)) = orig.split_last() {
+                    let Some(conv) = (conversions(o).into_iter()).find(|conv| new.ends_with(conv))
+                    else {
+                        break;
+                    };
+                    suffix.push(o);
+                    new = &new[..new.len() - conv.len()];
+                    orig = rest;
+                }
+                restored.extend(new);
+                restored.extend(suffix.into_iter().rev());
+                restored.into()
+            }
+            (Value::Char(mut chars), Value::Char(orig)) if chars.shape == orig.shape => {
+                for (c, &o) in chars.data.as_mut_slice().iter_mut().zip(&orig.data) {
+                    if *c != o && c.to_lowercase().eq(o.to_lowercase()) {
+                        *c = o;
+                    }
+                }
+                chars.into()
+            }
+            (Value::Box(mut boxes), Value::Box(orig)) if boxes.shape == orig.shape => {
+                for (Boxed(val), Boxed(o)) in boxes.data.as_mut_slice().iter_mut().zip(&orig.data) {
+                    *val = take(val).restore_case(o);
+                }
+                boxes.into()
+            }
+            (val, _) => val,
+        }
+    }
+    /// Normalize a string to Unicode Normalization Form C
+    pub fn nfc(&self, env: &Uiua) -> UiuaResult<Self> {
+        self.normalize("nfc", &|s| s.nfc().collect(), env)
+    }
+    /// Normalize a string to Unicode Normalization Form D
+    pub fn nfd(&self, env: &Uiua) -> UiuaResult<Self> {
+        self.normalize("nfd", &|s| s.nfd().collect(), env)
+    }
+    fn normalize(&self, name: &str, f: &dyn Fn(&str) -> String, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            Value::Char(chars) if chars.rank() <= 1 => {
+                let s: String = chars.data.iter().collect();
+                f(&s).into()
+            }
+            Value::Box(boxes) => {
+                let mut boxes = boxes.clone();
+                for Boxed(val) in boxes.data.as_mut_slice() {
+                    *val = val.normalize(name, f, env)?;
+                }
+                boxes.into()
+            }
+            Value::Char(chars) => {
+                return Err(env.error(format!(
+                    "Argument to {name} must be a string or box array of strings, \
+                    but it is a rank {} character array",
+                    chars.rank()
+                )))
+            }
+            val => {
+                return Err(env.error(format!(
+                    "Argument to {name} must be a string, but it is {}",
+                    val.type_name_plural()
+                )))
+            }
+        })
+    }
+    /// Get the display width of a string
+    pub fn text_width(&self, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            Value::Char(chars) if chars.rank() == 0 => {
+                (chars.data[0].width().unwrap_or(0) as f64).into()
+            }
+            Value::Char(chars) => {
+                let row_len = chars.shape[chars.rank() - 1];
+                let shape = Shape::from(&chars.shape[..chars.rank() - 1]);
+                let widths: EcoVec<f64> = if row_len == 0 {
+                    eco_vec![0.0; shape.iter().product()]
+                } else {
+                    (chars.data.chunks_exact(row_len))
+                        .map(|row| row.iter().collect::<String>().width() as f64)
+                        .collect()
+                };
+                Array::new(shape, widths).into()
+            }
+            Value::Box(boxes) => {
+                let mut widths = EcoVec::with_capacity(boxes.element_count());
+                for Boxed(val) in &boxes.data {
+                    let width = val.text_width(env)?;
+                    widths.push(width.as_num(env, "Boxed strings must be lists")?);
+                }
+                Array::new(boxes.shape.clone(), widths).into()
+            }
+            val => {
+                return Err(env.error(format!(
+                    "Argument to textwidth must be a string, but it is {}",
+                    val.type_name_plural()
+                )))
+            }
+        })
+    }
+}
+
 impl Value {
     pub(crate) fn first_min_index(&self, env: &Uiua) -> UiuaResult<Self> {
         self.generic_ref_env(
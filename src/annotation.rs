@@ -0,0 +1,235 @@
+//! Type and shape annotations for binding arguments
+
+use std::{collections::HashMap, fmt};
+
+use ecow::EcoString;
+use serde::*;
+
+use crate::{Shape, Value};
+
+/// Annotations of the types and shapes of a function's arguments
+///
+/// The first annotation is for the argument on top of the stack.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Annotation {
+    /// The annotations of each argument
+    pub args: Vec<ArgAnnotation>,
+}
+
+/// An annotation of the type and shape of a single argument
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ArgAnnotation {
+    /// The required element type
+    pub ty: Option<ElemType>,
+    /// The required shape pattern
+    pub shape: Option<Vec<Dim>>,
+}
+
+/// The element type of an array
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum ElemType {
+    Num,
+    Byte,
+    Complex,
+    Char,
+    Box,
+}
+
+/// A dimension in a shape pattern
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dim {
+    /// A dimension of an exact length
+    Exact(usize),
+    /// A named dimension, which must have the same length everywhere it appears
+    Named(EcoString),
+    /// Any single dimension
+    Any,
+    /// Any number of dimensions
+    Rest,
+}
+
+impl ElemType {
+    /// Get the element type from its name in an annotation
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "num" => ElemType::Num,
+            "byte" => ElemType::Byte,
+            "complex" => ElemType::Complex,
+            "char" => ElemType::Char,
+            "box" => ElemType::Box,
+            _ => return None,
+        })
+    }
+    /// Get the element type of a value
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Num(_) => ElemType::Num,
+            #[cfg(feature = "bytes")]
+            Value::Byte(_) => ElemType::Byte,
+            Value::Complex(_) => ElemType::Complex,
+            Value::Char(_) => ElemType::Char,
+            Value::Box(_) => ElemType::Box,
+        }
+    }
+}
+
+impl fmt::Display for ElemType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ElemType::Num => write!(f, "num"),
+            ElemType::Byte => write!(f, "byte"),
+            ElemType::Complex => write!(f, "complex"),
+            ElemType::Char => write!(f, "char"),
+            ElemType::Box => write!(f, "box"),
+        }
+    }
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dim::Exact(n) => write!(f, "{n}"),
+            Dim::Named(name) => write!(f, "{name}"),
+            Dim::Any => write!(f, "_"),
+            Dim::Rest => write!(f, ".."),
+        }
+    }
+}
+
+impl fmt::Display for ArgAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ty) = self.ty {
+            write!(f, "{ty}")?;
+        }
+        if let Some(shape) = &self.shape {
+            write!(f, "[")?;
+            for (i, dim) in shape.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{dim}")?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{arg}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What is known about an argument that is checked against an annotation
+#[derive(Debug, Clone)]
+pub(crate) struct ArgInfo {
+    pub ty: ElemType,
+    pub shape: Shape,
+    /// Whether a number array only contains bytes, if that is known
+    pub bytes: Option<bool>,
+}
+
+impl ArgInfo {
+    /// Get information about a value to check against an argument annotation
+    pub fn from_value(value: &Value, ann: &ArgAnnotation) -> Self {
+        let bytes = match value {
+            Value::Num(arr) if ann.ty == Some(ElemType::Byte) => {
+                Some((arr.data.iter()).all(|&n| n.fract() == 0.0 && (0.0..=255.0).contains(&n)))
+            }
+            _ => None,
+        };
+        ArgInfo {
+            ty: ElemType::of(value),
+            shape: value.shape().clone(),
+            bytes,
+        }
+    }
+}
+
+impl Annotation {
+    /// Check arguments against the annotation
+    ///
+    /// Arguments that are `None` are unknown and are not checked.
+    /// Returns whether every argument was known to match.
+    pub(crate) fn check(&self, args: &[Option<ArgInfo>]) -> Result<bool, String> {
+        let mut complete = true;
+        let mut named = HashMap::new();
+        for (i, (ann, arg)) in self.args.iter().zip(args).enumerate() {
+            let Some(arg) = arg else {
+                complete = false;
+                continue;
+            };
+            if let Some(ty) = ann.ty {
+                match (ty, arg.ty) {
+                    (ElemType::Num, ElemType::Num | ElemType::Byte) => {}
+                    (ElemType::Byte, ElemType::Num) if arg.bytes != Some(false) => {
+                        complete &= arg.bytes.is_some();
+                    }
+                    (a, b) if a == b => {}
+                    (_, actual) => {
+                        return Err(format!(
+                            "Argument {} should be a {ty} array, but it is a {actual} array",
+                            i + 1
+                        ))
+                    }
+                }
+            }
+            if let Some(pattern) = &ann.shape {
+                match_shape(pattern, &arg.shape, &mut named).map_err(|bound| {
+                    let mut message = format!(
+                        "Argument {} should have shape {}, but its shape is {}",
+                        i + 1,
+                        ArgAnnotation {
+                            ty: None,
+                            shape: Some(pattern.clone())
+                        },
+                        arg.shape
+                    );
+                    if let Some((name, len)) = bound {
+                        message.push_str(&format!(" and {name} is {len}"));
+                    }
+                    message
+                })?;
+            }
+        }
+        Ok(complete)
+    }
+}
+
+/// Match a shape against a pattern, binding named dimensions
+///
+/// On failure, returns the binding of the named dimension that did not match, if any.
+fn match_shape<'a>(
+    pattern: &'a [Dim],
+    shape: &[usize],
+    named: &mut HashMap<&'a str, usize>,
+) -> Result<(), Option<(&'a str, usize)>> {
+    let (front, back) = match pattern.iter().position(|dim| *dim == Dim::Rest) {
+        Some(i) if shape.len() + 1 >= pattern.len() => (&pattern[..i], &pattern[i + 1..]),
+        None if shape.len() == pattern.len() => (pattern, &[][..]),
+        _ => return Err(None),
+    };
+    let dims = (front.iter().zip(shape)).chain(back.iter().zip(&shape[shape.len() - back.len()..]));
+    for (dim, &len) in dims {
+        match dim {
+            Dim::Exact(n) if *n != len => return Err(None),
+            Dim::Named(name) => match named.get(name.as_str()) {
+                Some(&bound) if bound != len => return Err(Some((name.as_str(), bound))),
+                Some(_) => {}
+                None => {
+                    named.insert(name.as_str(), len);
+                }
+            },
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -1,12 +1,17 @@
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use dashmap::DashMap;
 use ecow::{eco_vec, EcoString, EcoVec};
 use serde::*;
 
 use crate::{
-    lex::Sp, CodeSpan, DynamicFunction, FuncSlice, Function, Ident, ImplPrimitive, InputSrc, Instr,
-    IntoInputSrc, Primitive, Signature, Span, TempStack, Uiua, UiuaResult, Value,
+    lex::Sp, Annotation, CodeSpan, DynamicFunction, FormatSpec, FuncSlice, Function, FunctionId,
+    Ident, ImplPrimitive, InputSrc, Instr, IntoInputSrc, Primitive, Purity, Signature, Span,
+    TempStack, Uiua, UiuaResult, Value,
 };
 
 /// A compiled Uiua assembly
@@ -21,6 +26,16 @@ pub struct Assembly {
     pub global_references: HashMap<Sp<Ident>, usize>,
     #[serde(skip)]
     pub(crate) dynamic_functions: EcoVec<DynFn>,
+    /// The purity of each function
+    ///
+    /// Functions that share an id, such as anonymous functions generated from the same span
+    /// or named functions in different modules, share the most impure of their purities.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        with = "purity_pairs"
+    )]
+    pub(crate) purity: HashMap<FunctionId, Purity>,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub(crate) import_inputs: HashMap<PathBuf, EcoString>,
     pub(crate) spans: EcoVec<Span>,
@@ -29,6 +44,25 @@ pub struct Assembly {
 
 type DynFn = Arc<dyn Fn(&mut Uiua) -> UiuaResult + Send + Sync + 'static>;
 
+/// Serialize the purity map as a list of pairs, because function ids are not strings
+mod purity_pairs {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        map: &HashMap<FunctionId, Purity>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<FunctionId, Purity>, D::Error> {
+        Vec::<(FunctionId, Purity)>::deserialize(deserializer)
+            .map(|pairs| pairs.into_iter().collect())
+    }
+}
+
 impl Default for Assembly {
     fn default() -> Self {
         Self {
@@ -39,6 +73,7 @@ impl Default for Assembly {
             bindings: EcoVec::new(),
             global_references: HashMap::new(),
             dynamic_functions: EcoVec::new(),
+            purity: HashMap::new(),
             inputs: Inputs::default(),
         }
     }
@@ -59,6 +94,13 @@ impl Assembly {
     pub fn instrs_mut(&mut self, slice: FuncSlice) -> &mut [Instr] {
         &mut self.instrs.make_mut()[slice.start..][..slice.len]
     }
+    /// Get the purity of the function with the given id
+    ///
+    /// Returns `None` if no function with the id was compiled into this assembly.
+    /// If several functions have the id, the most impure of their purities is returned.
+    pub fn purity(&self, id: &FunctionId) -> Option<Purity> {
+        self.purity.get(id).copied()
+    }
     pub(crate) fn bind_function(
         &mut self,
         index: usize,
@@ -100,6 +142,7 @@ impl Assembly {
             global,
             span,
             comment,
+            annotation: None,
         };
         if index < self.bindings.len() {
             self.bindings.make_mut()[index] = binding;
@@ -109,6 +152,7 @@ impl Assembly {
                     global: Global::Const(Value::default()),
                     span: None,
                     comment: None,
+                    annotation: None,
                 });
             }
             self.bindings.push(binding);
@@ -146,6 +190,9 @@ pub struct BindingInfo {
     #[allow(dead_code)]
     /// The comment preceding the binding
     pub comment: Option<Arc<str>>,
+    #[serde(skip)]
+    /// The annotation of the binding's arguments
+    pub annotation: Option<Arc<Annotation>>,
 }
 
 /// A type of global binding
@@ -161,6 +208,45 @@ pub enum Global {
     /// A module
     #[allow(missing_docs)]
     Module { module: PathBuf },
+    /// A module declared within a file
+    #[allow(missing_docs)]
+    ScopedModule { items: ModuleItems },
+    /// A tagged variant type
+    #[allow(missing_docs)]
+    Variants { variants: Vec<Variant> },
+}
+
+/// A variant of a tagged variant type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variant {
+    /// The name of the variant
+    pub name: Ident,
+    /// The number of payload fields the variant carries
+    pub fields: usize,
+}
+
+/// The names bound in a module
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModuleItems {
+    /// Map names to global indices
+    pub names: HashMap<Ident, usize>,
+    /// Names that can only be accessed from within the module
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub private: HashSet<Ident>,
+}
+
+impl ModuleItems {
+    /// Get the global index of an item that can be accessed from outside the module
+    pub fn get_public(&self, item: &str, module_name: &str) -> Result<usize, String> {
+        match self.names.get(item) {
+            Some(_) if self.private.contains(item) => Err(format!(
+                "Item `{item}` in module `{module_name}` is private. \
+                Bind it with ← instead of ↚ to make it public"
+            )),
+            Some(&index) => Ok(index),
+            None => Err(format!("Item `{item}` not found in module `{module_name}`")),
+        }
+    }
 }
 
 impl Global {
@@ -170,7 +256,7 @@ impl Global {
             Self::Const(_) => Some(Signature::new(0, 1)),
             Self::Func(func) => Some(func.signature()),
             Self::Sig(sig) => Some(*sig),
-            Self::Module { .. } => None,
+            Self::Module { .. } | Self::ScopedModule { .. } | Self::Variants { .. } => None,
         }
     }
     /// Check if the global is a once-bound constant
@@ -271,7 +357,7 @@ enum InstrRep {
     Call(usize),
     PushFunc(Function),
     Switch(usize, Signature, usize),
-    Format(EcoVec<EcoString>, usize),
+    Format(EcoVec<EcoString>, EcoVec<FormatSpec>, usize),
     Label(EcoString, usize),
     Dynamic(DynamicFunction),
     PushLocals(usize, usize),
@@ -282,6 +368,7 @@ enum InstrRep {
     PopTempFunctions(usize),
     GetTempFunction(usize, Signature, usize),
     TouchStack(usize, usize),
+    CheckAnnotation(Annotation, usize),
     PushTemp(TempStack, usize, usize),
     PopTemp(TempStack, usize, usize),
     CopyToTemp(TempStack, usize, usize),
@@ -312,7 +399,7 @@ impl From<Instr> for InstrRep {
             Instr::Call(index) => Self::Call(index),
             Instr::PushFunc(func) => Self::PushFunc(func),
             Instr::Switch { count, sig, span } => Self::Switch(count, sig, span),
-            Instr::Format { parts, span } => Self::Format(parts, span),
+            Instr::Format { parts, specs, span } => Self::Format(parts, specs, span),
             Instr::Label { label, span } => Self::Label(label, span),
             Instr::Dynamic(func) => Self::Dynamic(func),
             Instr::PushLocals { count, span } => Self::PushLocals(count, span),
@@ -325,6 +412,9 @@ impl From<Instr> for InstrRep {
                 Self::GetTempFunction(offset, sig, span)
             }
             Instr::TouchStack { count, span } => Self::TouchStack(count, span),
+            Instr::CheckAnnotation { annotation, span } => {
+                Self::CheckAnnotation((*annotation).clone(), span)
+            }
             Instr::PushTemp { stack, count, span } => Self::PushTemp(stack, count, span),
             Instr::PopTemp { stack, count, span } => Self::PopTemp(stack, count, span),
             Instr::CopyToTemp { stack, count, span } => Self::CopyToTemp(stack, count, span),
@@ -356,7 +446,7 @@ impl From<InstrRep> for Instr {
             InstrRep::Call(index) => Self::Call(index),
             InstrRep::PushFunc(func) => Self::PushFunc(func),
             InstrRep::Switch(count, sig, span) => Self::Switch { count, sig, span },
-            InstrRep::Format(parts, span) => Self::Format { parts, span },
+            InstrRep::Format(parts, specs, span) => Self::Format { parts, specs, span },
             InstrRep::Label(label, span) => Self::Label { label, span },
             InstrRep::Dynamic(func) => Self::Dynamic(func),
             InstrRep::PushLocals(count, span) => Self::PushLocals { count, span },
@@ -369,6 +459,10 @@ impl From<InstrRep> for Instr {
                 Self::GetTempFunction { offset, sig, span }
             }
             InstrRep::TouchStack(count, span) => Self::TouchStack { count, span },
+            InstrRep::CheckAnnotation(annotation, span) => Self::CheckAnnotation {
+                annotation: annotation.into(),
+                span,
+            },
             InstrRep::PushTemp(stack, count, span) => Self::PushTemp { stack, count, span },
             InstrRep::PopTemp(stack, count, span) => Self::PopTemp { stack, count, span },
             InstrRep::CopyToTemp(stack, count, span) => Self::CopyToTemp { stack, count, span },
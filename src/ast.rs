@@ -5,9 +5,9 @@ use std::fmt;
 
 use crate::{
     function::{FunctionId, Signature},
-    lex::{CodeSpan, Sp},
+    lex::{CodeSpan, FormatFragments, Sp},
     parse::ident_modifier_args,
-    Ident, Primitive,
+    Annotation, FormatSpec, Ident, Primitive,
 };
 
 /// A top-level item
@@ -19,6 +19,12 @@ pub enum Item {
     Binding(Binding),
     /// A test scope
     TestScope(Sp<Vec<Item>>),
+    /// A named module scope
+    Module(Sp<ScopedModule>),
+    /// A record type declaration
+    Record(Sp<Record>),
+    /// A tagged variant type declaration
+    Variants(Sp<VariantType>),
 }
 
 impl Item {
@@ -26,6 +32,9 @@ impl Item {
     pub fn span(&self) -> CodeSpan {
         match self {
             Item::TestScope(items) => items.span.clone(),
+            Item::Module(module) => module.span.clone(),
+            Item::Record(record) => record.span.clone(),
+            Item::Variants(variants) => variants.span.clone(),
             Item::Words(words) => {
                 let first = (words.iter().flatten().next())
                     .expect("empty words")
@@ -49,8 +58,12 @@ pub struct Binding {
     pub name: Sp<Ident>,
     /// The span of the arrow
     pub arrow_span: CodeSpan,
+    /// Whether the binding can be accessed from outside its module
+    pub public: bool,
     /// The signature
     pub signature: Option<Sp<Signature>>,
+    /// The annotation of the arguments' types and shapes
+    pub annotation: Option<Sp<Annotation>>,
     /// The code
     pub words: Vec<Sp<Word>>,
 }
@@ -66,6 +79,46 @@ impl Binding {
     }
 }
 
+/// A named module declared within a file
+#[derive(Debug, Clone)]
+pub struct ScopedModule {
+    /// The name of the module
+    pub name: Sp<Ident>,
+    /// The items in the module
+    pub items: Vec<Item>,
+}
+
+/// A record type declaration
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// The name of the record type
+    pub name: Sp<Ident>,
+    /// The span of the `~`
+    pub tilde_span: CodeSpan,
+    /// The names of the fields
+    pub fields: Vec<Sp<Ident>>,
+}
+
+/// A tagged variant type declaration
+#[derive(Debug, Clone)]
+pub struct VariantType {
+    /// The name of the variant type
+    pub name: Sp<Ident>,
+    /// The span of the `~`
+    pub tilde_span: CodeSpan,
+    /// The variants
+    pub variants: Vec<VariantDecl>,
+}
+
+/// A variant in a tagged variant type declaration
+#[derive(Debug, Clone)]
+pub struct VariantDecl {
+    /// The name of the variant
+    pub name: Sp<Ident>,
+    /// The names of the variant's payload fields
+    pub fields: Vec<Sp<Ident>>,
+}
+
 /// A word
 #[derive(Clone)]
 #[allow(missing_docs)]
@@ -74,8 +127,8 @@ pub enum Word {
     Char(String),
     String(String),
     Label(String),
-    FormatString(Vec<String>),
-    MultilineString(Vec<Sp<Vec<String>>>),
+    FormatString(FormatFragments),
+    MultilineString(Vec<Sp<FormatFragments>>),
     Ident(Ident),
     Strand(Vec<Sp<Word>>),
     Array(Arr),
@@ -150,6 +203,22 @@ impl Word {
     }
 }
 
+/// Write escaped format string fragments with their placeholders and specifiers
+fn fmt_format_fragments(parts: &FormatFragments, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, part) in parts.frags.iter().enumerate() {
+        if i > 0 {
+            write!(f, "_")?;
+            let spec = &parts.specs[i - 1];
+            if *spec != FormatSpec::default() {
+                write!(f, "{{:{spec}}}")?;
+            }
+        }
+        let escaped = format!("{part:?}");
+        write!(f, "{}", &escaped[1..escaped.len() - 1])?;
+    }
+    Ok(())
+}
+
 impl fmt::Debug for Word {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -159,21 +228,13 @@ impl fmt::Debug for Word {
             Word::Label(label) => write!(f, "${label}"),
             Word::FormatString(parts) => {
                 write!(f, "$\"")?;
-                for part in parts {
-                    let escaped = format!("{part:?}");
-                    let part = &escaped[1..escaped.len() - 1];
-                    write!(f, "{part}")?;
-                }
+                fmt_format_fragments(parts, f)?;
                 write!(f, "\"")
             }
             Word::MultilineString(lines) => {
                 for line in lines {
                     write!(f, "$ ")?;
-                    for part in &line.value {
-                        let escaped = format!("{part:?}");
-                        let part = &escaped[1..escaped.len() - 1];
-                        write!(f, "{part}")?;
-                    }
+                    fmt_format_fragments(&line.value, f)?;
                 }
                 Ok(())
             }
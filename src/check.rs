@@ -1,12 +1,13 @@
-use std::{borrow::Cow, cmp::Ordering, fmt};
+use std::{borrow::Cow, cmp::Ordering, fmt, iter::repeat};
 
 use enum_iterator::Sequence;
 
 use crate::{
+    annotation::{ArgInfo, ElemType},
     array::Array,
     function::{Function, FunctionId, Instr, Signature},
     value::Value,
-    FuncSlice, ImplPrimitive, Primitive, TempStack,
+    FuncSlice, ImplPrimitive, Primitive, Shape, TempStack,
 };
 
 const START_HEIGHT: usize = 16;
@@ -32,6 +33,20 @@ pub(crate) fn instrs_temp_signatures(
     Ok(env.temp_signatures())
 }
 
+/// Get what is known about the values on top of the stack after some instructions
+///
+/// The first item is for the top of the stack.
+pub(crate) fn instrs_arg_info(instrs: &[Instr], count: usize) -> Vec<Option<ArgInfo>> {
+    let Ok(env) = VirtualEnv::from_instrs(instrs) else {
+        return vec![None; count];
+    };
+    (env.stack.iter().rev())
+        .map(BasicValue::arg_info)
+        .chain(repeat(None))
+        .take(count)
+        .collect()
+}
+
 /// An environment that emulates the runtime but only keeps track of the stack.
 struct VirtualEnv<'a> {
     stack: Vec<BasicValue>,
@@ -86,6 +101,7 @@ impl fmt::Display for SigCheckError {
 enum BasicValue {
     Num(f64),
     Arr(Vec<Self>),
+    Known(ElemType, Shape),
     Other,
     Unknown(usize),
 }
@@ -101,14 +117,44 @@ impl BasicValue {
                 Value::Num(n) => n.data.iter().map(|n| BasicValue::Num(*n)).collect(),
                 #[cfg(feature = "bytes")]
                 Value::Byte(b) => b.data.iter().map(|b| BasicValue::Num(*b as f64)).collect(),
-                Value::Complex(c) => c.data.iter().map(|_| BasicValue::Other).collect(),
-                Value::Char(c) => c.data.iter().map(|_| BasicValue::Other).collect(),
-                Value::Box(b) => b.data.iter().map(|_| BasicValue::Other).collect(),
+                Value::Complex(_) | Value::Char(_) | Value::Box(_) => {
+                    let item = BasicValue::Known(ElemType::of(value), Shape::scalar());
+                    vec![item; value.row_count()]
+                }
             })
         } else {
-            BasicValue::Other
+            BasicValue::Known(ElemType::of(value), value.shape().clone())
+        }
+    }
+    fn type_and_shape(&self) -> Option<(ElemType, Shape)> {
+        match self {
+            BasicValue::Num(_) => Some((ElemType::Num, Shape::scalar())),
+            BasicValue::Known(ty, shape) => Some((*ty, shape.clone())),
+            BasicValue::Arr(items) => {
+                let mut rows = items.iter().map(BasicValue::type_and_shape);
+                let (ty, mut shape) = match rows.next() {
+                    Some(row) => row?,
+                    None => (ElemType::Num, Shape::scalar()),
+                };
+                for row in rows {
+                    if row? != (ty, shape.clone()) {
+                        return None;
+                    }
+                }
+                shape.insert(0, items.len());
+                Some((ty, shape))
+            }
+            BasicValue::Other | BasicValue::Unknown(_) => None,
         }
     }
+    fn arg_info(&self) -> Option<ArgInfo> {
+        let (ty, shape) = self.type_and_shape()?;
+        Some(ArgInfo {
+            ty,
+            shape,
+            bytes: None,
+        })
+    }
 }
 
 impl FromIterator<f64> for BasicValue {
@@ -208,15 +254,20 @@ impl<'a> VirtualEnv<'a> {
                 self.pop()?;
             }
             Instr::BeginArray => self.array_stack.push(self.stack.len()),
-            Instr::EndArray { .. } => {
+            Instr::EndArray { boxed, .. } => {
                 let bottom = self
                     .array_stack
                     .pop()
                     .ok_or("EndArray without BeginArray")?;
                 let mut items: Vec<_> = self.stack.drain(bottom..).collect();
                 self.set_min_height();
-                items.reverse();
-                self.stack.push(BasicValue::Arr(items));
+                if *boxed {
+                    let shape = crate::Shape::from(items.len());
+                    self.stack.push(BasicValue::Known(ElemType::Box, shape));
+                } else {
+                    items.reverse();
+                    self.stack.push(BasicValue::Arr(items));
+                }
             }
             Instr::Call(_) => {
                 let sig = self.pop_func()?.signature();
@@ -282,12 +333,18 @@ impl<'a> VirtualEnv<'a> {
                 }
                 self.handle_args_outputs(sig.args + 1, sig.outputs)?;
             }
-            Instr::Format { parts, .. } => {
-                self.handle_args_outputs(parts.len().saturating_sub(1), 1)?
-            }
+            Instr::Format { specs, .. } => self.handle_args_outputs(specs.len(), 1)?,
             Instr::Dynamic(f) => self.handle_sig(f.signature)?,
             Instr::Unpack { count, .. } => self.handle_args_outputs(1, *count)?,
             Instr::TouchStack { count, .. } => self.handle_args_outputs(*count, *count)?,
+            Instr::CheckAnnotation { annotation, .. } => {
+                let mut args = Vec::with_capacity(annotation.args.len());
+                for _ in 0..annotation.args.len() {
+                    args.push(self.pop()?);
+                }
+                self.set_min_height();
+                self.stack.extend(args.into_iter().rev());
+            }
             Instr::DropTemp { count, stack, .. } => {
                 for _ in 0..*count {
                     self.pop_temp(*stack)?;
@@ -482,6 +539,11 @@ impl<'a> VirtualEnv<'a> {
                     let b = self.pop()?;
                     self.set_min_height();
                     match (a, b) {
+                        (BasicValue::Known(_, shape), _) | (_, BasicValue::Known(_, shape))
+                            if !shape.is_empty() =>
+                        {
+                            self.stack.push(BasicValue::Other);
+                        }
                         (BasicValue::Arr(mut a), BasicValue::Arr(b)) => {
                             a.extend(b);
                             self.stack.push(BasicValue::Arr(a));
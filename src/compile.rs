@@ -1,6 +1,7 @@
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
     fmt, fs,
+    iter::repeat_n,
     mem::{replace, take},
     panic::{catch_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
@@ -12,15 +13,18 @@ use ecow::{eco_vec, EcoString, EcoVec};
 use crate::{
     algorithm::invert::{invert_instrs, under_instrs},
     ast::*,
-    check::{instrs_signature, SigCheckError},
+    check::{instrs_arg_info, instrs_signature, SigCheckError},
     constants, example_ua,
     function::*,
     lex::{CodeSpan, Sp, Span},
     optimize::{optimize_instrs, optimize_instrs_mut},
+    package::{dependency_root, resolve_package_import},
     parse::{count_placeholders, ident_modifier_args, parse, split_words, unsplit_words},
-    Array, Assembly, Boxed, Diagnostic, DiagnosticKind, Global, Ident, ImplPrimitive, InputSrc,
-    IntoInputSrc, IntoSysBackend, Primitive, RunMode, SafeSys, SysBackend, SysOp, Uiua, UiuaError,
-    UiuaResult, Value,
+    purity::instrs_purity,
+    std_lib::{resolve_std_import, std_module},
+    Annotation, Array, Assembly, Boxed, Diagnostic, DiagnosticKind, Global, Ident, ImplPrimitive,
+    InputSrc, IntoInputSrc, IntoSysBackend, ModuleItems, Primitive, RunMode, SafeSys, SysBackend,
+    SysOp, Uiua, UiuaError, UiuaResult, Value, Variant,
 };
 
 /// The Uiua compiler
@@ -39,7 +43,9 @@ pub struct Compiler {
     /// The paths of files currently being imported (used to detect import cycles)
     pub(crate) current_imports: Vec<PathBuf>,
     /// The bindings of imported files
-    pub(crate) imports: HashMap<PathBuf, HashMap<Ident, usize>>,
+    pub(crate) imports: HashMap<PathBuf, ModuleItems>,
+    /// The local roots of package dependencies, by manifest directory and name
+    pub(crate) dependency_roots: HashMap<(PathBuf, String), PathBuf>,
     /// Accumulated errors
     pub(crate) errors: Vec<UiuaError>,
     /// Accumulated diagnostics
@@ -61,6 +67,7 @@ impl Default for Compiler {
             mode: RunMode::All,
             current_imports: Vec::new(),
             imports: HashMap::new(),
+            dependency_roots: HashMap::new(),
             errors: Vec::new(),
             diagnostics: BTreeSet::new(),
             print_diagnostics: false,
@@ -85,21 +92,46 @@ impl AsMut<Assembly> for Compiler {
 pub(crate) struct Scope {
     /// Map local names to global indices
     pub names: HashMap<Ident, usize>,
+    /// Names bound with a private binding
+    pub private_names: HashSet<Ident>,
     /// Whether to allow experimental features
     pub experimental: bool,
     /// The stack height between top-level statements
     pub stack_height: Result<usize, Sp<SigCheckError>>,
     /// The stack of referenced locals
     pub locals: Vec<HashSet<usize>>,
+    /// The names bound in this scope, in order
+    pub bound: Vec<BoundName>,
+    /// The spans of the lines that produced the values on the stack between top-level statements
+    pub value_spans: Vec<CodeSpan>,
+    /// Whether some code in this scope was not compiled because of the run mode
+    pub skipped_code: bool,
+}
+
+/// A name bound in a scope
+#[derive(Clone)]
+pub(crate) struct BoundName {
+    /// The name and the span where it is bound
+    pub name: Sp<Ident>,
+    /// The global index the name refers to
+    pub index: usize,
+    /// Whether the binding is public
+    pub public: bool,
+    /// Whether the binding is an import
+    pub import: bool,
 }
 
 impl Default for Scope {
     fn default() -> Self {
         Self {
             names: HashMap::new(),
+            private_names: HashSet::new(),
             experimental: false,
             stack_height: Ok(0),
             locals: Vec::new(),
+            bound: Vec::new(),
+            value_spans: Vec::new(),
+            skipped_code: false,
         }
     }
 }
@@ -171,13 +203,23 @@ impl Compiler {
         &mut self,
         f: impl FnOnce(&mut Self) -> UiuaResult<T>,
     ) -> UiuaResult<HashMap<Ident, usize>> {
+        self.in_module_scope(f).map(|items| items.names)
+    }
+    /// Run in a scoped context like [`Compiler::in_scope`], keeping track of which names are private
+    fn in_module_scope<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> UiuaResult<T>,
+    ) -> UiuaResult<ModuleItems> {
         let experimental = self.scope.experimental;
         self.higher_scopes.push(take(&mut self.scope));
         self.scope.experimental = experimental;
         let res = f(self);
         let scope = replace(&mut self.scope, self.higher_scopes.pop().unwrap());
         res?;
-        Ok(scope.names)
+        Ok(ModuleItems {
+            names: scope.names,
+            private: scope.private_names,
+        })
     }
     fn load_impl(&mut self, input: &str, src: InputSrc) -> UiuaResult<&mut Self> {
         let instrs_start = self.asm.instrs.len();
@@ -201,6 +243,11 @@ impl Compiler {
 
         if let InputSrc::File(_) = &src {
             self.current_imports.pop();
+            // Strings may be compiled incrementally, so their bindings may be used later
+            if matches!(res, Ok(Ok(()))) && self.errors.is_empty() {
+                self.unused_diagnostics(!self.higher_scopes.is_empty());
+                self.flush_diagnostics();
+            }
         }
         match res {
             Err(e) | Ok(Err(e)) => {
@@ -258,8 +305,34 @@ code:
         let prev_com = prev_comment.take();
         match item {
             Item::TestScope(items) => {
+                if self.mode == RunMode::Normal {
+                    self.scope.skipped_code = true;
+                }
                 self.in_scope(|env| env.items(items.value, true))?;
             }
+            Item::Module(module) => {
+                let ScopedModule { name, items } = module.value;
+                let items = self.in_module_scope(|env| {
+                    env.items(items, in_test)?;
+                    env.unused_diagnostics(true);
+                    Ok(())
+                })?;
+                let global_index = self.next_global;
+                self.next_global += 1;
+                self.asm.add_global_at(
+                    global_index,
+                    Global::ScopedModule { items },
+                    Some(name.span.clone()),
+                    prev_com,
+                );
+                self.scope.names.insert(name.value.clone(), global_index);
+                self.scope.bound.push(BoundName {
+                    name,
+                    index: global_index,
+                    public: true,
+                    import: false,
+                });
+            }
             Item::Words(mut lines) => {
                 if lines.iter().flatten().all(|w| !w.value.is_code()) {
                     let mut comment = String::new();
@@ -284,6 +357,9 @@ code:
                     RunMode::Test => in_test,
                     RunMode::All => true,
                 };
+                if !can_run {
+                    self.scope.skipped_code = true;
+                }
                 lines = unsplit_words(lines.into_iter().flat_map(split_words));
                 for line in lines {
                     if line.is_empty() {
@@ -304,6 +380,9 @@ code:
                                 if let Ok(height) = &mut self.scope.stack_height {
                                     *height = (*height + sig.outputs).saturating_sub(sig.args);
                                 }
+                                let values = &mut self.scope.value_spans;
+                                values.truncate(values.len().saturating_sub(sig.args));
+                                values.extend(repeat_n(span.clone(), sig.outputs));
                             }
                             Err(e) => self.scope.stack_height = Err(span.sp(e)),
                         }
@@ -326,9 +405,330 @@ code:
                     self.binding(binding, prev_com)?;
                 }
             }
+            Item::Record(record) => {
+                let can_run = match self.mode {
+                    RunMode::Normal => !in_test,
+                    RunMode::All | RunMode::Test => true,
+                };
+                if can_run {
+                    self.record(record, prev_com)?;
+                }
+            }
+            Item::Variants(variants) => {
+                let can_run = match self.mode {
+                    RunMode::Normal => !in_test,
+                    RunMode::All | RunMode::Test => true,
+                };
+                if can_run {
+                    self.variant_type(variants, prev_com)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Bind a record type's constructor, field getters, and field setters
+    fn record(&mut self, record: Sp<Record>, comment: Option<Arc<str>>) -> UiuaResult {
+        let Record {
+            name,
+            tilde_span,
+            fields,
+        } = record.value;
+        if fields.is_empty() {
+            self.add_error(
+                record.span,
+                format!("Record `{}` must have at least one field", name.value),
+            );
+            return Ok(());
+        }
+        let mut seen = HashSet::new();
+        for field in &fields {
+            if !seen.insert(&field.value) {
+                self.add_error(
+                    field.span.clone(),
+                    format!(
+                        "Record `{}` has more than one `{}` field",
+                        name.value, field.value
+                    ),
+                );
+            }
+        }
+        // Getters and setters must not silently replace other bindings
+        let mut shadows = false;
+        for field in &fields {
+            for generated in [field.value.clone(), format!("Set{}", field.value).into()] {
+                if self.scope.names.contains_key(&generated) {
+                    shadows = true;
+                    self.add_error(
+                        field.span.clone(),
+                        format!(
+                            "Record `{}` would replace the existing binding `{generated}`. \
+                            Declare one of them in a scoped module to keep both.",
+                            name.value
+                        ),
+                    );
+                }
+            }
+        }
+        if shadows {
+            return Ok(());
+        }
+        // Generated bindings are not checked for being unused
+        let bound_len = self.scope.bound.len();
+        // Constructor
+        let span = &name.span;
+        let identity = identity_words(span, fields.len());
+        let field_names: Vec<&str> = fields.iter().map(|field| field.value.as_str()).collect();
+        let mut constructor_comment = format!(
+            "Construct a `{}` record from its fields: {}",
+            name.value,
+            field_names.join(" ")
+        );
+        if let Some(comment) = comment {
+            constructor_comment = format!("{comment}\n\n{constructor_comment}");
+        }
+        let constructor = Binding {
+            name: name.clone(),
+            arrow_span: tilde_span.clone(),
+            public: true,
+            signature: None,
+            annotation: None,
+            words: vec![span.clone().sp(Word::Array(Arr {
+                lines: vec![vec![identity]],
+                boxes: true,
+                closed: true,
+            }))],
+        };
+        self.binding(constructor, Some(constructor_comment.into()))?;
+        for (i, field) in fields.into_iter().enumerate() {
+            let span = &field.span;
+            // Getter
+            let getter_words = vec![
+                modified_word(
+                    span,
+                    Primitive::Un,
+                    vec![span.clone().sp(Word::Primitive(Primitive::Box))],
+                ),
+                span.clone().sp(Word::Primitive(Primitive::Pick)),
+                span.clone().sp(Word::Number(i.to_string(), i as f64)),
+            ];
+            let getter = Binding {
+                name: field.clone(),
+                arrow_span: tilde_span.clone(),
+                public: true,
+                signature: None,
+                annotation: None,
+                words: getter_words.clone(),
+            };
+            let comment = format!(
+                "Get the `{}` field of a `{}` record\n\nUse it with ⍜ to update the field",
+                field.value, name.value
+            );
+            self.binding(getter, Some(comment.into()))?;
+            // Setter
+            let getter_func = span.clone().sp(Word::Func(Func {
+                id: FunctionId::Anonymous(span.clone()),
+                signature: None,
+                lines: vec![getter_words],
+                closed: true,
+            }));
+            let replace = modified_word(
+                span,
+                Primitive::Gap,
+                vec![span.clone().sp(Word::Primitive(Primitive::Identity))],
+            );
+            let setter = Binding {
+                name: span.clone().sp(format!("Set{}", field.value).into()),
+                arrow_span: tilde_span.clone(),
+                public: true,
+                signature: None,
+                annotation: None,
+                words: vec![
+                    modified_word(span, Primitive::Under, vec![getter_func, replace]),
+                    span.clone().sp(Word::Primitive(Primitive::Flip)),
+                ],
+            };
+            let comment = format!(
+                "Set the `{}` field of a `{}` record",
+                field.value, name.value
+            );
+            self.binding(setter, Some(comment.into()))?;
+        }
+        self.scope.bound.truncate(bound_len);
+        Ok(())
+    }
+    /// Bind a tagged variant type and the constructors of its variants
+    ///
+    /// A variant value is a list of boxes whose first item is the variant's tag
+    fn variant_type(&mut self, variants: Sp<VariantType>, comment: Option<Arc<str>>) -> UiuaResult {
+        let VariantType {
+            name,
+            tilde_span,
+            variants: decls,
+        } = variants.value;
+        let mut seen = HashSet::new();
+        for decl in &decls {
+            if !seen.insert(&decl.name.value) {
+                self.add_error(
+                    decl.name.span.clone(),
+                    format!(
+                        "Variant type `{}` has more than one `{}` variant",
+                        name.value, decl.name.value
+                    ),
+                );
+            }
+        }
+        // Generated bindings are not checked for being unused
+        let bound_len = self.scope.bound.len();
+        let mut variants = Vec::new();
+        for (tag, decl) in decls.into_iter().enumerate() {
+            let span = &decl.name.span;
+            let mut line = vec![span.clone().sp(Word::Number(tag.to_string(), tag as f64))];
+            if !decl.fields.is_empty() {
+                line.push(identity_words(span, decl.fields.len()));
+            }
+            let field_names: Vec<&str> = decl.fields.iter().map(|f| f.value.as_str()).collect();
+            let mut comment = format!(
+                "Construct the `{}` variant of `{}`",
+                decl.name.value, name.value
+            );
+            if !field_names.is_empty() {
+                comment.push_str(&format!(" from its fields: {}", field_names.join(" ")));
+            }
+            let constructor = Binding {
+                name: decl.name.clone(),
+                arrow_span: tilde_span.clone(),
+                public: true,
+                signature: None,
+                annotation: None,
+                words: vec![span.clone().sp(Word::Array(Arr {
+                    lines: vec![line],
+                    boxes: true,
+                    closed: true,
+                }))],
+            };
+            self.binding(constructor, Some(comment.into()))?;
+            variants.push(Variant {
+                name: decl.name.value,
+                fields: decl.fields.len(),
+            });
         }
+        self.scope.bound.truncate(bound_len);
+        let global_index = self.next_global;
+        self.next_global += 1;
+        self.asm.add_global_at(
+            global_index,
+            Global::Variants { variants },
+            Some(name.span),
+            comment,
+        );
+        self.scope.private_names.remove(&name.value);
+        self.scope.names.insert(name.value, global_index);
         Ok(())
     }
+    /// Compile a match on a tagged variant type
+    ///
+    /// Each branch is called with the payload fields of its variant
+    fn variant_match(
+        &mut self,
+        modified: Modified,
+        name: Ident,
+        variants: Vec<Variant>,
+        call: bool,
+    ) -> UiuaResult {
+        let span = modified.modifier.span.clone();
+        let Some(operand) = modified.code_operands().next().cloned() else {
+            return Err(self.fatal_error(span, format!("Match on `{name}` requires a function")));
+        };
+        let operand_span = operand.span.clone();
+        let branches = match operand.value {
+            Word::Switch(sw) => sw.branches,
+            Word::Func(func) => vec![operand_span.clone().sp(func)],
+            _ => vec![operand_span.clone().sp(Func {
+                id: FunctionId::Anonymous(operand_span.clone()),
+                signature: None,
+                lines: vec![vec![operand]],
+                closed: true,
+            })],
+        };
+        if branches.len() < variants.len() {
+            let missing: Vec<String> = (variants[branches.len()..].iter())
+                .map(|variant| format!("`{}`", variant.name))
+                .collect();
+            return Err(self.fatal_error(
+                span.merge(operand_span),
+                format!(
+                    "Match on `{name}` is missing {} for {}",
+                    if missing.len() == 1 {
+                        "a branch"
+                    } else {
+                        "branches"
+                    },
+                    missing.join(", ")
+                ),
+            ));
+        }
+        if branches.len() > variants.len() {
+            return Err(self.fatal_error(
+                span.merge(operand_span),
+                format!(
+                    "Match on `{name}` has {} branches, but `{name}` only has {} variants",
+                    branches.len(),
+                    variants.len()
+                ),
+            ));
+        }
+        // Each branch unpacks its variant's fields and discards the tag
+        let branches = (branches.into_iter().zip(&variants))
+            .map(|(branch, variant)| {
+                let span = &branch.span;
+                let unpack = modified_word(
+                    span,
+                    Primitive::Un,
+                    vec![span.clone().sp(Word::Array(Arr {
+                        lines: vec![vec![identity_words(span, variant.fields + 1)]],
+                        boxes: true,
+                        closed: true,
+                    }))],
+                );
+                let mut lines = vec![vec![
+                    span.clone().sp(Word::Primitive(Primitive::Pop)),
+                    unpack,
+                ]];
+                lines.extend(branch.value.lines);
+                span.clone().sp(Func {
+                    id: FunctionId::Anonymous(span.clone()),
+                    signature: None,
+                    lines,
+                    closed: true,
+                })
+            })
+            .collect();
+        // Dispatch on the tag
+        let words = vec![
+            operand_span.sp(Word::Switch(Switch {
+                branches,
+                closed: true,
+            })),
+            modified_word(
+                &span,
+                Primitive::Un,
+                vec![span.clone().sp(Word::Primitive(Primitive::Box))],
+            ),
+            span.clone().sp(Word::Primitive(Primitive::First)),
+            span.clone().sp(Word::Primitive(Primitive::Dup)),
+        ];
+        if call {
+            self.words(words, true)
+        } else {
+            let func = Func {
+                id: FunctionId::Anonymous(span.clone()),
+                signature: None,
+                lines: vec![words],
+                closed: true,
+            };
+            self.func(func, span, false)
+        }
+    }
     #[must_use]
     pub(crate) fn add_function<I>(&mut self, id: FunctionId, sig: Signature, instrs: I) -> Function
     where
@@ -345,12 +745,20 @@ code:
         if len > 1 {
             (self.asm.instrs).push(Instr::Comment(format!("{id})").into()));
         }
+        let purity = instrs_purity(&self.asm.instrs[start..][..len], &self.asm);
+        let recorded = self.asm.purity.entry(id.clone()).or_default();
+        *recorded = (*recorded).max(purity);
         Function::new(id, sig, FuncSlice { start, len })
     }
     fn binding(&mut self, binding: Binding, comment: Option<Arc<str>>) -> UiuaResult {
-        let name = binding.name.value;
+        let name = binding.name.value.clone();
         let span = &binding.name.span;
         let placeholder_count = count_placeholders(&binding.words);
+        if binding.public {
+            self.scope.private_names.remove(&name);
+        } else {
+            self.scope.private_names.insert(name.clone());
+        }
 
         let make_fn = |mut instrs: EcoVec<Instr>, sig: Signature, comp: &mut Self| {
             // Diagnostic for function that doesn't consume its arguments
@@ -420,21 +828,20 @@ code:
                         match item {
                             Value::Char(arr) if arr.rank() == 1 => {
                                 let item: String = arr.data.iter().copied().collect();
-                                if let Some(&index) = self.imports[&module].get(item.as_str()) {
-                                    self.scope.names.insert(name.clone(), index);
-                                    if let Some(s) = self.asm.bindings[index].global.signature() {
-                                        sig = Some(s);
-                                    } else {
-                                        self.add_error(
-                                            span.clone(),
-                                            "Cannot define a signature for a module rebind",
-                                        )
+                                match self.imports[&module].get_public(&item, &path) {
+                                    Ok(index) => {
+                                        self.scope.names.insert(name.clone(), index);
+                                        if let Some(s) = self.asm.bindings[index].global.signature()
+                                        {
+                                            sig = Some(s);
+                                        } else {
+                                            self.add_error(
+                                                span.clone(),
+                                                "Cannot define a signature for a module rebind",
+                                            )
+                                        }
                                     }
-                                } else {
-                                    self.add_error(
-                                        span.clone(),
-                                        format!("Item `{item}` not found in module `{path}`"),
-                                    )
+                                    Err(e) => self.add_error(span.clone(), e),
                                 }
                             }
                             _ => self.add_error(span.clone(), "Import item must be a string"),
@@ -459,6 +866,7 @@ code:
                                 sig = Signature::new(0, 1);
                             }
                             *height = height.saturating_sub(1);
+                            self.scope.value_spans.pop();
                         }
                         Err(sp) => {
                             let sp = sp.clone();
@@ -537,7 +945,7 @@ code:
             }
             Err(e) => {
                 if is_import {
-                } else if let Some(sig) = binding.signature {
+                } else if let Some(sig) = &binding.signature {
                     // Binding is a normal function
                     let func = make_fn(instrs, sig.value, self);
                     self.compile_bind_function(&name, global_index, func, span_index, comment)?;
@@ -556,6 +964,29 @@ code:
                 }
             }
         }
+        // Attach the annotation
+        if let Some((annotation, sig)) = binding.annotation.zip(binding.signature) {
+            if annotation.value.args.len() != sig.value.args {
+                self.add_error(
+                    annotation.span,
+                    format!(
+                        "The annotation has {} arguments, but the signature has {}",
+                        annotation.value.args.len(),
+                        sig.value.args
+                    ),
+                );
+            } else if let Some(binding) = self.asm.bindings.make_mut().get_mut(global_index) {
+                binding.annotation = Some(annotation.value.into());
+            }
+        }
+        if let Some(&index) = self.scope.names.get(&name) {
+            self.scope.bound.push(BoundName {
+                name: binding.name,
+                index,
+                public: binding.public,
+                import: is_import,
+            });
+        }
         Ok(())
     }
     pub(crate) fn compile_bind_sig(
@@ -597,13 +1028,16 @@ code:
         Ok(())
     }
     pub(crate) fn import_compile(&mut self, path: &Path, span: &CodeSpan) -> UiuaResult<PathBuf> {
-        let path = self.resolve_import_path(Path::new(&path));
+        let path = (self.resolve_import_path(Path::new(&path)))
+            .map_err(|e| self.fatal_error(span.clone(), e))?;
         if self.asm.import_inputs.get(&path).is_some() {
             return Ok(path);
         }
         let bytes = fs::read(&path)
             .or_else(|e| {
-                if path.ends_with(Path::new("example.ua")) {
+                if let Some(src) = std_module(&path) {
+                    Ok(src.as_bytes().to_vec())
+                } else if path.ends_with(Path::new("example.ua")) {
                     Ok(example_ua(|ex| ex.as_bytes().to_vec()))
                 } else {
                     Err(e)
@@ -621,24 +1055,50 @@ code:
             ));
         }
         if !self.imports.contains_key(&path) {
-            let import = self.in_scope(|env| env.load_str_src(&input, &path).map(drop))?;
+            let import = self.in_module_scope(|env| env.load_str_src(&input, &path).map(drop))?;
             self.imports.insert(path.clone(), import);
         }
         Ok(path)
     }
     /// Resolve a declared import path relative to the path of the file that is being executed
-    pub(crate) fn resolve_import_path(&self, path: &Path) -> PathBuf {
-        let target = if let Some(parent) = self.current_imports.last().and_then(|p| p.parent()) {
+    ///
+    /// If there is no such file, paths that start with `std` refer to bundled standard library modules,
+    /// and other paths may name a dependency in the nearest package manifest.
+    pub(crate) fn resolve_import_path(&mut self, path: &Path) -> Result<PathBuf, String> {
+        let parent = self.current_imports.last().and_then(|p| p.parent());
+        let mut target = if let Some(parent) = parent {
             parent.join(path)
         } else {
             path.to_path_buf()
         };
-        let base = Path::new(".");
-        if let (Ok(canon_target), Ok(canon_base)) = (target.canonicalize(), base.canonicalize()) {
-            pathdiff::diff_paths(canon_target, canon_base).unwrap_or(target)
-        } else {
-            pathdiff::diff_paths(&target, base).unwrap_or(target)
+        if !target.exists() {
+            if let Some(resolved) = resolve_std_import(path) {
+                return resolved;
+            }
+            let dir = parent.unwrap_or(Path::new("."));
+            let roots = &mut self.dependency_roots;
+            let resolved = resolve_package_import(dir, path, |manifest_dir, name, dep| {
+                let key = (manifest_dir.to_path_buf(), name.to_string());
+                if let Some(root) = roots.get(&key) {
+                    return Ok(root.clone());
+                }
+                let root = dependency_root(manifest_dir, name, dep)?;
+                roots.insert(key, root.clone());
+                Ok(root)
+            });
+            if let Some(resolved) = resolved {
+                target = resolved?;
+            }
         }
+        let base = Path::new(".");
+        Ok(
+            if let (Ok(canon_target), Ok(canon_base)) = (target.canonicalize(), base.canonicalize())
+            {
+                pathdiff::diff_paths(canon_target, canon_base).unwrap_or(target)
+            } else {
+                pathdiff::diff_paths(&target, base).unwrap_or(target)
+            },
+        )
     }
     fn compile_words(&mut self, mut words: Vec<Sp<Word>>, call: bool) -> UiuaResult<EcoVec<Instr>> {
         words = unsplit_words(split_words(words))
@@ -647,10 +1107,65 @@ code:
             .collect();
 
         self.new_functions.push(EcoVec::new());
-        self.words(words, call)?;
+        let res = self.words(words, call);
         self.flush_diagnostics();
+        res?;
         Ok(self.new_functions.pop().unwrap())
     }
+    /// Emit diagnostics for the bindings of the current scope that are never used
+    ///
+    /// Public bindings of modules are not checked, as they may be used by importers.
+    /// For modules, values left on the stack by top-level code are reported as well.
+    fn unused_diagnostics(&mut self, module: bool) {
+        let mut diagnostics = Vec::new();
+        if !self.scope.skipped_code {
+            let referenced: HashSet<(&InputSrc, &Ident, usize)> = (self.asm.global_references)
+                .iter()
+                .map(|(name, &index)| (&name.span.src, &name.value, index))
+                .collect();
+            for bound in &self.scope.bound {
+                let name = &bound.name;
+                if referenced.contains(&(&name.span.src, &name.value, bound.index))
+                    || module && bound.public
+                {
+                    continue;
+                }
+                let (message, kind) = match (bound.import, bound.public) {
+                    (true, _) => (
+                        format!("Import `{}` is never used", name.value),
+                        DiagnosticKind::Warning,
+                    ),
+                    (false, false) => (
+                        format!("Private binding `{}` is never used", name.value),
+                        DiagnosticKind::Warning,
+                    ),
+                    (false, true) => (
+                        format!("Binding `{}` is never used", name.value),
+                        DiagnosticKind::Advice,
+                    ),
+                };
+                diagnostics.push((message, kind, name.span.clone()));
+            }
+        }
+        if module && self.scope.stack_height.is_ok() {
+            let mut values = self.scope.value_spans.iter().peekable();
+            while let Some(span) = values.next() {
+                let mut count = 1;
+                while values.next_if_eq(&span).is_some() {
+                    count += 1;
+                }
+                let message = if count == 1 {
+                    "This line leaves a value on the stack at the end of the module".into()
+                } else {
+                    format!("This line leaves {count} values on the stack at the end of the module")
+                };
+                diagnostics.push((message, DiagnosticKind::Warning, span.clone()));
+            }
+        }
+        for (message, kind, span) in diagnostics {
+            self.emit_diagnostic(message, kind, span);
+        }
+    }
     fn flush_diagnostics(&mut self) {
         if self.print_diagnostics {
             for diagnostic in self.take_diagnostics() {
@@ -704,33 +1219,35 @@ code:
             if let Some(next) = words.peek() {
                 // Handle imports
                 if let Word::Ident(name) = &next.value {
-                    if let Some(index) = self.scope.names.get(name) {
-                        if let Global::Module { module } = &self.asm.bindings[*index].global {
-                            if let Word::String(item_name) = &word.value {
-                                let index = self.imports[module]
-                                    .get(item_name.as_str())
-                                    .copied()
-                                    .ok_or_else(|| {
-                                    self.fatal_error(
-                                        next.span.clone(),
-                                        format!(
-                                            "Item `{item_name}` not found in module `{}`",
-                                            module.display()
-                                        ),
-                                    )
-                                })?;
-                                self.global_index(index, next.span.clone(), false);
-                                words.next();
-                                continue;
-                            } else {
-                                self.add_error(
-                                    next.span.clone(),
-                                    format!(
-                                        "Expected a string after `{name}` \
-                                        to specify an item to import",
-                                    ),
-                                );
-                            }
+                    let module_index = (self.scope.names.get(name))
+                        .or_else(|| self.higher_scopes.last()?.names.get(name))
+                        .copied();
+                    if let Some((module_index, module_name)) =
+                        module_index.and_then(|i| Some((i, self.module_name(i)?)))
+                    {
+                        if let Word::String(item_name) = &word.value {
+                            let index = (self.module_items(module_index).unwrap())
+                                .get_public(item_name, &module_name)
+                                .map_err(|e| self.fatal_error(next.span.clone(), e))?;
+                            (self.asm.global_references)
+                                .insert(next.span.clone().sp(name.clone()), module_index);
+                            (self.asm.global_references).insert(
+                                string_contents_span(&word.span).sp(item_name.as_str().into()),
+                                index,
+                            );
+                            // Modifiers can only be rebound, not called
+                            let call = call && !item_name.ends_with('!');
+                            self.global_index(index, next.span.clone(), call);
+                            words.next();
+                            continue;
+                        } else {
+                            self.add_error(
+                                next.span.clone(),
+                                format!(
+                                    "Expected a string after `{name}` \
+                                    to specify an item to import",
+                                ),
+                            );
                         }
                     }
                 }
@@ -753,6 +1270,34 @@ code:
         }
         Ok(())
     }
+    /// Get the name of the module bound at a global index
+    fn module_name(&self, index: usize) -> Option<String> {
+        let binding = &self.asm.bindings[index];
+        match &binding.global {
+            Global::Module { module } => Some(module.display().to_string()),
+            Global::ScopedModule { .. } => {
+                let span = binding.span.as_ref()?;
+                Some(span.as_str(&self.asm.inputs, |s| s.into()))
+            }
+            _ => None,
+        }
+    }
+    /// Get the name and global index of the variant type matched by a modifier name
+    fn variant_type_index(&self, ident: &str) -> Option<(Ident, usize)> {
+        let name: Ident = ident.strip_suffix('!')?.into();
+        let index = (self.scope.names.get(&name))
+            .or_else(|| self.higher_scopes.last()?.names.get(&name))
+            .copied()?;
+        matches!(self.asm.bindings[index].global, Global::Variants { .. }).then_some((name, index))
+    }
+    /// Get the items of the module bound at a global index
+    pub(crate) fn module_items(&self, index: usize) -> Option<&ModuleItems> {
+        match &self.asm.bindings[index].global {
+            Global::Module { module } => self.imports.get(module),
+            Global::ScopedModule { items } => Some(items),
+            _ => None,
+        }
+    }
     /// Push an instruction to the current function being compiled
     ///
     /// Also performs some optimizations if the instruction and the previous
@@ -833,10 +1378,11 @@ code:
                 }
             }
             Word::FormatString(frags) => {
-                let signature = Signature::new(frags.len() - 1, 1);
-                let parts = frags.into_iter().map(Into::into).collect();
+                let signature = Signature::new(frags.specs.len(), 1);
+                let parts = frags.frags.into_iter().map(Into::into).collect();
+                let specs = frags.specs.into_iter().collect();
                 let span = self.add_span(word.span.clone());
-                let instr = Instr::Format { parts, span };
+                let instr = Instr::Format { parts, specs, span };
                 if call {
                     self.push_instr(instr)
                 } else {
@@ -846,26 +1392,25 @@ code:
                 }
             }
             Word::MultilineString(lines) => {
-                let signature = Signature::new(
-                    lines.iter().map(|l| l.value.len().saturating_sub(1)).sum(),
-                    1,
-                );
+                let signature = Signature::new(lines.iter().map(|l| l.value.specs.len()).sum(), 1);
                 let span = self.add_span(word.span.clone());
                 let mut curr_part = EcoString::new();
                 let mut parts = EcoVec::new();
+                let mut specs = EcoVec::new();
                 for (l, line) in lines.into_iter().enumerate() {
                     if l > 0 {
                         curr_part.push('\n');
                     }
-                    for (f, frag) in line.value.into_iter().enumerate() {
+                    for (f, frag) in line.value.frags.into_iter().enumerate() {
                         if f > 0 {
                             parts.push(take(&mut curr_part));
                         }
                         curr_part.push_str(&frag);
                     }
+                    specs.extend(line.value.specs);
                 }
                 parts.push(curr_part);
-                let instr = Instr::Format { parts, span };
+                let instr = Instr::Format { parts, specs, span };
                 if call {
                     self.push_instr(instr)
                 } else {
@@ -1072,6 +1617,26 @@ code:
     }
     fn global_index(&mut self, index: usize, span: CodeSpan, call: bool) {
         let global = self.asm.bindings[index].global.clone();
+        if let Some(annotation) = self.asm.bindings[index].annotation.clone() {
+            if call {
+                self.check_annotation(annotation, span.clone());
+            } else if let Global::Func(f) = global {
+                // Wrap the function so that it checks its arguments
+                // wherever it is called
+                let span_index = self.add_span(span.clone());
+                let instrs = [
+                    Instr::CheckAnnotation {
+                        annotation,
+                        span: span_index,
+                    },
+                    Instr::PushFunc(f.clone()),
+                    Instr::Call(span_index),
+                ];
+                let f = self.add_function(FunctionId::Anonymous(span), f.signature(), instrs);
+                self.push_instr(Instr::PushFunc(f));
+                return;
+            }
+        }
         match global {
             Global::Const(val) if call => self.push_instr(Instr::push(val)),
             Global::Const(val) => {
@@ -1112,7 +1677,29 @@ code:
                 );
                 self.push_instr(Instr::PushFunc(f));
             }
-            Global::Module { .. } => self.add_error(span, "Cannot import module item here."),
+            Global::Module { .. } | Global::ScopedModule { .. } => {
+                self.add_error(span, "Cannot import module item here.")
+            }
+            Global::Variants { .. } => self.add_error(
+                span,
+                "A variant type can only be used as a modifier \
+                to match on its variants, like `Name!(…|…)`",
+            ),
+        }
+    }
+    /// Check the arguments of a call against an annotation
+    ///
+    /// Arguments that cannot be inferred are checked at runtime.
+    fn check_annotation(&mut self, annotation: Arc<Annotation>, span: CodeSpan) {
+        let instrs = self.new_functions.last().map_or(&[][..], |instrs| instrs);
+        let args = instrs_arg_info(instrs, annotation.args.len());
+        match annotation.check(&args) {
+            Ok(true) => {}
+            Ok(false) => {
+                let span = self.add_span(span);
+                self.push_instr(Instr::CheckAnnotation { annotation, span });
+            }
+            Err(message) => self.emit_diagnostic(message, DiagnosticKind::Warning, span),
         }
     }
     fn func(&mut self, func: Func, span: CodeSpan, call: bool) -> UiuaResult {
@@ -1240,6 +1827,18 @@ code:
     }
     #[allow(clippy::collapsible_match)]
     fn modified(&mut self, modified: Modified, call: bool) -> UiuaResult {
+        // Match on a variant type
+        if let Modifier::Ident(ident) = &modified.modifier.value {
+            if let Some((name, index)) = self.variant_type_index(ident) {
+                let span = modified.modifier.span.clone();
+                (self.asm.global_references).insert(span.sp(name.clone()), index);
+                if let Global::Variants { variants } = &self.asm.bindings[index].global {
+                    let variants = variants.clone();
+                    return self.variant_match(modified, name, variants, call);
+                }
+            }
+        }
+
         let op_count = modified.code_operands().count();
 
         // De-sugar switched
@@ -2006,6 +2605,8 @@ code:
     /// Take all diagnostics
     ///
     /// These are only available if `print_diagnostics` is `false`
+    ///
+    /// Diagnostics emitted while running are available from [`Uiua::take_diagnostics`].
     pub fn take_diagnostics(&mut self) -> BTreeSet<Diagnostic> {
         take(&mut self.diagnostics)
     }
@@ -2101,3 +2702,212 @@ fn words_look_pervasive(words: &[Sp<Word>]) -> bool {
         _ => false,
     })
 }
+
+/// Make a word for a primitive modifier applied to some operands
+fn modified_word(span: &CodeSpan, prim: Primitive, operands: Vec<Sp<Word>>) -> Sp<Word> {
+    span.clone().sp(Word::Modified(Box::new(Modified {
+        modifier: span.clone().sp(Modifier::Primitive(prim)),
+        operands,
+    })))
+}
+
+/// Make a word for an identity function with the given number of arguments
+///
+/// This is `∘` wrapped in `count - 1` `⊙`s.
+fn identity_words(span: &CodeSpan, count: usize) -> Sp<Word> {
+    let mut word = span.clone().sp(Word::Primitive(Primitive::Identity));
+    for _ in 1..count {
+        word = modified_word(span, Primitive::Dip, vec![word]);
+    }
+    word
+}
+
+/// Get the span of the contents of a string literal, without its quotes
+fn string_contents_span(span: &CodeSpan) -> CodeSpan {
+    let mut span = span.clone();
+    if span.end.char_pos - span.start.char_pos >= 2 {
+        span.start.char_pos += 1;
+        span.start.byte_pos += 1;
+        span.start.col += 1;
+        span.end.char_pos -= 1;
+        span.end.byte_pos -= 1;
+        span.end.col -= 1;
+    }
+    span
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    fn compile_error(comp: UiuaResult<&mut Compiler>) -> String {
+        match comp {
+            Ok(_) => panic!("Compilation succeeded"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn private_items() {
+        let private = "Item `Sq` in module `Geometry` is private";
+        let module = "---Geometry\nSq ↚ ×.\nCircle ← ×π Sq\n---\n";
+        let err = compile_error(Compiler::new().load_str(&format!("{module}Geometry \"Sq\" 2")));
+        assert!(err.contains(private), "{err}");
+        Compiler::new()
+            .load_str(&format!("{module}Geometry \"Circle\" 2"))
+            .unwrap();
+
+        let dir = env::temp_dir().join(format!("uiua-private-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Geometry"), "Sq ↚ ×.\nCircle ← ×π Sq\n").unwrap();
+        fs::write(dir.join("main.ua"), "Sq ← &i \"Geometry\" \"Sq\"\n").unwrap();
+        let err = compile_error(Compiler::new().load_file(dir.join("main.ua")));
+        assert!(err.contains(private), "{err}");
+        _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Get the messages of diagnostics with the code they refer to
+    #[allow(clippy::mutable_key_type)]
+    fn spanned_diagnostics(diagnostics: BTreeSet<Diagnostic>) -> Vec<(String, String)> {
+        (diagnostics.into_iter())
+            .map(|diag| {
+                let Span::Code(span) = &diag.span else {
+                    panic!("Diagnostic has no code span: {}", diag.message);
+                };
+                let code = span.as_str(&diag.inputs, |s| s.to_string());
+                (diag.message, code)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn annotation_diagnostics() {
+        let def = "MatMul ← |2 num[n m] num[m k] | ⊞(/+×)⊙⍉\n";
+
+        // Arguments that are known at compile time are checked statically
+        let mut comp = Compiler::new();
+        comp.load_str(&format!("{def}MatMul [1_2_3] [1_2]"))
+            .unwrap();
+        let diags = spanned_diagnostics(comp.take_diagnostics());
+        assert_eq!(diags.len(), 1, "{diags:?}");
+        assert_eq!(
+            diags[0].0,
+            "Argument 2 should have shape [m k], but its shape is [1 × 2] and m is 3"
+        );
+        assert_eq!(diags[0].1, "MatMul");
+        assert!(comp
+            .finish()
+            .instrs
+            .iter()
+            .all(|instr| !matches!(instr, Instr::CheckAnnotation { .. })));
+
+        // Other arguments are checked at runtime
+        let def = "Join ← |2 num[n] num[n] | ⊂\n";
+        let mut env = Uiua::with_safe_sys();
+        let mut comp = (env.run_str(&format!("{def}Join ⇡⌊+3⚂ ⇡⌊+2⚂"))).unwrap();
+        assert!(comp.take_diagnostics().is_empty());
+        let diags = spanned_diagnostics(env.take_diagnostics());
+        assert_eq!(
+            diags,
+            [(
+                "Argument 2 should have shape [n], but its shape is [2] and n is 3".into(),
+                "Join".into()
+            )]
+        );
+
+        // Functions passed to modifiers are checked when they are called
+        let inc = "Inc ← |1 num | +1\n";
+        for code in ["∵Inc \"abc\"", "≡Inc [\"ab\" \"cd\"]"] {
+            let mut env = Uiua::with_safe_sys();
+            let mut comp = (env.run_str(&format!("{inc}{code}"))).unwrap();
+            assert!(comp.take_diagnostics().is_empty());
+            let diags = spanned_diagnostics(env.take_diagnostics());
+            assert_eq!(diags.len(), 1, "{code}: {diags:?}");
+            assert_eq!(diags[0].1, "Inc");
+        }
+
+        // Matching calls have no diagnostics
+        let mut env = Uiua::with_safe_sys();
+        let mut comp = (env.run_str(&format!("{def}Join ⇡⌊+2⚂ ⇡⌊+2⚂\nJoin [1 2] [3 4]"))).unwrap();
+        assert!(comp.take_diagnostics().is_empty());
+        assert!(env.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn record_shadowing() {
+        let err = compile_error(Compiler::new().load_str("Person ~ Name Age\nPet ~ Kind Name"));
+        assert!(
+            err.contains("Record `Pet` would replace the existing binding `Name`"),
+            "{err}"
+        );
+        let err = compile_error(Compiler::new().load_str("SetAge ← 5\nPerson ~ Name Age"));
+        assert!(err.contains("existing binding `SetAge`"), "{err}");
+        // Records in different scopes do not conflict
+        Compiler::new()
+            .load_str("Person ~ Name Age\n---Pets\nPet ~ Kind Name\n---")
+            .unwrap();
+    }
+
+    #[test]
+    fn unused_diagnostics() {
+        let dir = env::temp_dir().join(format!("uiua-unused-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.ua"), "F ← +1\n3 4\n").unwrap();
+        let main = "\
+Lib ← &i \"lib.ua\"
+Helper ↚ +1
+Used ↚ ×2
+Used 3
+X ← 5
+---M
+G ← +2
+5
+---
+M \"G\" 1";
+        let mut comp = Compiler::new();
+        comp.load_str_src(main, &dir.join("main.ua")).unwrap();
+        let mut diags = spanned_diagnostics(comp.take_diagnostics());
+        diags.sort();
+        let expected: Vec<(String, String)> = [
+            ("Binding `X` is never used", "X"),
+            ("Import `Lib` is never used", "Lib"),
+            ("Private binding `Helper` is never used", "Helper"),
+            (
+                "This line leaves 2 values on the stack at the end of the module",
+                "3 4",
+            ),
+            (
+                "This line leaves a value on the stack at the end of the module",
+                "5",
+            ),
+        ]
+        .map(|(message, code)| (message.into(), code.into()))
+        .into();
+        assert_eq!(diags, expected);
+        _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn std_imports() {
+        let dir = env::temp_dir().join(format!("uiua-std-test-{}", process::id()));
+        fs::create_dir_all(dir.join("std")).unwrap();
+        // A real file takes precedence over a bundled module
+        fs::write(dir.join("std/strings.ua"), "Split ← 5\n").unwrap();
+        fs::write(
+            dir.join("main.ua"),
+            "S ← &i \"std/strings.ua\"\nM ← &i \"std/matrix\"\nS \"Split\"\n⧻M \"Identity\" 3\n",
+        )
+        .unwrap();
+        let mut comp = Compiler::new();
+        comp.load_file(dir.join("main.ua")).unwrap();
+        let mut env = Uiua::with_safe_sys();
+        env.run_asm(comp.finish()).unwrap();
+        let stack: Vec<f64> = (env.take_stack().into_iter())
+            .map(|val| val.as_num(&env, "").unwrap())
+            .collect();
+        assert_eq!(stack, [5.0, 3.0]);
+        _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -535,6 +535,37 @@ impl<'a> Formatter<'a> {
                 self.format_items(&items.value);
                 self.output.push_str("---");
             }
+            Item::Module(module) => {
+                self.prev_import_function = None;
+                self.output.push_str("---");
+                self.output.push_str(&module.value.name.value);
+                self.output.push('\n');
+                self.format_items(&module.value.items);
+                self.output.push_str("---");
+            }
+            Item::Record(record) => {
+                self.prev_import_function = None;
+                self.output.push_str(&record.value.name.value);
+                self.output.push_str(" ~");
+                for field in &record.value.fields {
+                    self.output.push(' ');
+                    self.output.push_str(&field.value);
+                }
+            }
+            Item::Variants(variants) => {
+                self.prev_import_function = None;
+                self.output.push_str(&variants.value.name.value);
+                self.output.push_str(" ~");
+                for (i, variant) in variants.value.variants.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push_str(" |");
+                    }
+                    for name in [&variant.name].into_iter().chain(&variant.fields) {
+                        self.output.push(' ');
+                        self.output.push_str(&name.value);
+                    }
+                }
+            }
             Item::Words(lines) => {
                 self.prev_import_function = None;
                 let lines = unsplit_words(lines.iter().cloned().flat_map(split_words));
@@ -563,13 +594,17 @@ impl<'a> Formatter<'a> {
                 }
 
                 self.output.push_str(&binding.name.value);
-                self.output.push_str(" ←");
+                self.output
+                    .push_str(if binding.public { " ←" } else { " ↚" });
                 if !binding.words.is_empty() || binding.signature.is_some() {
                     self.output.push(' ');
                 }
                 if let Some(sig) = &binding.signature {
                     self.format_signature('|', sig.value, true);
                 }
+                if let Some(annotation) = &binding.annotation {
+                    self.output.push_str(&format!("{} | ", annotation.value));
+                }
                 let span = binding
                     .words
                     .first()
@@ -0,0 +1,381 @@
+//! Format specifiers for format string placeholders
+
+use std::{fmt, str::FromStr};
+
+use serde::*;
+
+use crate::{grid_fmt::GridFmt, Boxed, Complex, Uiua, UiuaResult, Value};
+
+/// A format specifier for a placeholder in a format string
+///
+/// Specifiers are written in braces after a `:` following the placeholder, as in `$"_{:>8.2}"`.
+/// The syntax is `[[fill]align][+][#][0][width][,][.precision][type]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct FormatSpec {
+    /// The character used to pad the value to the width
+    pub fill: Option<char>,
+    /// The alignment of the value within the width
+    pub align: Option<FormatAlign>,
+    /// Whether to always show the sign of a number
+    pub sign: bool,
+    /// Whether to prefix hexadecimal, binary, and octal numbers with `0x`, `0b`, or `0o`
+    pub alternate: bool,
+    /// Whether to pad numbers with zeros after the sign
+    pub zero: bool,
+    /// The minimum number of characters
+    pub width: Option<usize>,
+    /// Whether to separate groups of digits with commas
+    pub grouping: bool,
+    /// The number of digits after the decimal point,
+    /// or the maximum number of characters of a string
+    pub precision: Option<usize>,
+    /// The numeric notation
+    pub kind: FormatKind,
+}
+
+/// The alignment of a formatted value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum FormatAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// The numeric notation of a formatted value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum FormatKind {
+    #[default]
+    Default,
+    LowerHex,
+    UpperHex,
+    Binary,
+    Octal,
+    LowerExp,
+    UpperExp,
+}
+
+impl FormatSpec {
+    /// Parse a format specifier from the start of some characters
+    ///
+    /// Returns the specifier and the number of characters it spans.
+    /// Parsing stops at the first character that cannot continue the specifier.
+    pub fn parse(chars: &[char]) -> (Self, usize) {
+        let mut spec = FormatSpec::default();
+        let mut i = 0;
+        let align = |c: Option<&char>| match c? {
+            '<' => Some(FormatAlign::Left),
+            '^' => Some(FormatAlign::Center),
+            '>' => Some(FormatAlign::Right),
+            _ => None,
+        };
+        if let Some(a) = align(chars.get(1)) {
+            spec.fill = Some(chars[0]);
+            spec.align = Some(a);
+            i = 2;
+        } else if let Some(a) = align(chars.first()) {
+            spec.align = Some(a);
+            i = 1;
+        }
+        if chars.get(i) == Some(&'+') {
+            spec.sign = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'#') {
+            spec.alternate = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            spec.zero = true;
+            i += 1;
+        }
+        let parse_num = |i: &mut usize| {
+            let start = *i;
+            while chars.get(*i).is_some_and(char::is_ascii_digit) {
+                *i += 1;
+            }
+            let digits: String = chars[start..*i].iter().collect();
+            digits.parse().ok()
+        };
+        spec.width = parse_num(&mut i);
+        if chars.get(i) == Some(&',') {
+            spec.grouping = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+            i += 1;
+            spec.precision = parse_num(&mut i);
+        }
+        spec.kind = match chars.get(i) {
+            Some('x') => FormatKind::LowerHex,
+            Some('X') => FormatKind::UpperHex,
+            Some('b') => FormatKind::Binary,
+            Some('o') => FormatKind::Octal,
+            Some('e') => FormatKind::LowerExp,
+            Some('E') => FormatKind::UpperExp,
+            _ => FormatKind::Default,
+        };
+        if spec.kind != FormatKind::Default {
+            i += 1;
+        }
+        (spec, i)
+    }
+    /// Format a value according to this specifier
+    pub fn format(&self, val: &Value, env: &Uiua) -> UiuaResult<String> {
+        #[cfg(feature = "bytes")]
+        let is_num = matches!(val, Value::Num(_) | Value::Byte(_) | Value::Complex(_));
+        #[cfg(not(feature = "bytes"))]
+        let is_num = matches!(val, Value::Num(_) | Value::Complex(_));
+        let s = match val {
+            Value::Num(arr) => self.format_list(arr.rank(), arr.data.iter().copied(), env)?,
+            #[cfg(feature = "bytes")]
+            Value::Byte(arr) => {
+                self.format_list(arr.rank(), arr.data.iter().map(|&b| b as f64), env)?
+            }
+            Value::Complex(arr) if arr.rank() == 0 => self.format_complex(arr.data[0], env)?,
+            Value::Box(arr) if arr.rank() == 0 => {
+                let Boxed(val) = arr.as_scalar().unwrap();
+                return self.format(val, env);
+            }
+            val => {
+                if self.kind != FormatKind::Default || self.sign || self.grouping {
+                    return Err(env.error(format!(
+                        "Numeric format specifier {self} cannot be used with {}",
+                        val.type_name_plural()
+                    )));
+                }
+                let s = val.format();
+                match self.precision {
+                    Some(prec) => s.chars().take(prec).collect(),
+                    None => s,
+                }
+            }
+        };
+        Ok(self.pad(s, is_num))
+    }
+    fn format_list(
+        &self,
+        rank: usize,
+        mut nums: impl Iterator<Item = f64>,
+        env: &Uiua,
+    ) -> UiuaResult<String> {
+        match rank {
+            0 => self.format_num(nums.next().unwrap(), env),
+            1 => {
+                let mut s = String::from("[");
+                for (i, n) in nums.enumerate() {
+                    if i > 0 {
+                        s.push(' ');
+                    }
+                    s.push_str(&self.format_num(n, env)?);
+                }
+                s.push(']');
+                Ok(s)
+            }
+            _ => Err(env.error(format!(
+                "Format specifier {self} can only be used with scalars and lists, \
+                but the array is rank {rank}"
+            ))),
+        }
+    }
+    fn format_complex(&self, c: Complex, env: &Uiua) -> UiuaResult<String> {
+        let mut s = self.format_num(c.re, env)?;
+        s.push(if c.im < 0.0 { '-' } else { '+' });
+        let im = FormatSpec {
+            sign: false,
+            ..*self
+        };
+        s.push_str(&im.format_num(c.im.abs(), env)?);
+        s.push('i');
+        Ok(s)
+    }
+    fn format_num(&self, n: f64, env: &Uiua) -> UiuaResult<String> {
+        let sign = if n < 0.0 {
+            "¯"
+        } else if self.sign {
+            "+"
+        } else {
+            ""
+        };
+        let abs = n.abs();
+        let (prefix, digits) = if abs.is_nan() {
+            ("", "NaN".to_string())
+        } else if abs.is_infinite() {
+            ("", "∞".to_string())
+        } else {
+            let int = || {
+                if abs.fract() == 0.0 && abs <= u128::MAX as f64 {
+                    Ok(abs as u128)
+                } else {
+                    Err(env.error(format!(
+                        "Format specifier {self} can only be used with integers, \
+                        but {} is not an integer",
+                        Value::from(n).format()
+                    )))
+                }
+            };
+            let (prefix, digits) = match self.kind {
+                FormatKind::Default => (
+                    "",
+                    match self.precision {
+                        Some(prec) => format!("{abs:.prec$}"),
+                        None => abs.grid_string(false),
+                    },
+                ),
+                FormatKind::LowerExp => (
+                    "",
+                    match self.precision {
+                        Some(prec) => format!("{abs:.prec$e}"),
+                        None => format!("{abs:e}"),
+                    },
+                ),
+                FormatKind::UpperExp => (
+                    "",
+                    match self.precision {
+                        Some(prec) => format!("{abs:.prec$E}"),
+                        None => format!("{abs:E}"),
+                    },
+                ),
+                FormatKind::LowerHex => ("0x", format!("{:x}", int()?)),
+                FormatKind::UpperHex => ("0x", format!("{:X}", int()?)),
+                FormatKind::Binary => ("0b", format!("{:b}", int()?)),
+                FormatKind::Octal => ("0o", format!("{:o}", int()?)),
+            };
+            let digits = if self.grouping {
+                let group_size = match self.kind {
+                    FormatKind::Default | FormatKind::LowerExp | FormatKind::UpperExp => 3,
+                    _ => 4,
+                };
+                group_digits(&digits, group_size)
+            } else {
+                digits
+            };
+            (if self.alternate { prefix } else { "" }, digits)
+        };
+        let mut s = format!("{sign}{prefix}");
+        if self.zero && self.align.is_none() {
+            if let Some(width) = self.width {
+                let len = s.chars().count() + digits.chars().count();
+                for _ in len..width {
+                    s.push('0');
+                }
+            }
+        }
+        s.push_str(&digits);
+        Ok(s)
+    }
+    fn pad(&self, s: String, is_num: bool) -> String {
+        let Some(width) = self.width else {
+            return s;
+        };
+        let len = s.chars().count();
+        if len >= width {
+            return s;
+        }
+        let padding = width - len;
+        let fill = self.fill.unwrap_or(' ');
+        let align = self.align.unwrap_or(if is_num {
+            FormatAlign::Right
+        } else {
+            FormatAlign::Left
+        });
+        let (left, right) = match align {
+            FormatAlign::Left => (0, padding),
+            FormatAlign::Center => (padding / 2, padding - padding / 2),
+            FormatAlign::Right => (padding, 0),
+        };
+        let mut padded = String::with_capacity(s.len() + padding);
+        for _ in 0..left {
+            padded.push(fill);
+        }
+        padded.push_str(&s);
+        for _ in 0..right {
+            padded.push(fill);
+        }
+        padded
+    }
+}
+
+/// Separate groups of digits in the integer part of a number with commas
+fn group_digits(digits: &str, group_size: usize) -> String {
+    let int_len = digits
+        .find(|c: char| !c.is_ascii_hexdigit() || group_size == 3 && !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    let (int, rest) = digits.split_at(int_len);
+    let mut grouped = String::with_capacity(digits.len() + int_len / group_size);
+    for (i, c) in int.chars().enumerate() {
+        if i > 0 && (int_len - i) % group_size == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped.push_str(rest);
+    grouped
+}
+
+impl fmt::Display for FormatSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(fill) = self.fill {
+            write!(f, "{fill}")?;
+        }
+        match self.align {
+            Some(FormatAlign::Left) => write!(f, "<")?,
+            Some(FormatAlign::Center) => write!(f, "^")?,
+            Some(FormatAlign::Right) => write!(f, ">")?,
+            None => {}
+        }
+        if self.sign {
+            write!(f, "+")?;
+        }
+        if self.alternate {
+            write!(f, "#")?;
+        }
+        if self.zero {
+            write!(f, "0")?;
+        }
+        if let Some(width) = self.width {
+            write!(f, "{width}")?;
+        }
+        if self.grouping {
+            write!(f, ",")?;
+        }
+        if let Some(precision) = self.precision {
+            write!(f, ".{precision}")?;
+        }
+        match self.kind {
+            FormatKind::Default => Ok(()),
+            FormatKind::LowerHex => write!(f, "x"),
+            FormatKind::UpperHex => write!(f, "X"),
+            FormatKind::Binary => write!(f, "b"),
+            FormatKind::Octal => write!(f, "o"),
+            FormatKind::LowerExp => write!(f, "e"),
+            FormatKind::UpperExp => write!(f, "E"),
+        }
+    }
+}
+
+impl FromStr for FormatSpec {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        match FormatSpec::parse(&chars) {
+            (spec, len) if len == chars.len() => Ok(spec),
+            _ => Err(format!("Invalid format specifier {s:?}")),
+        }
+    }
+}
+
+impl From<FormatSpec> for String {
+    fn from(spec: FormatSpec) -> Self {
+        spec.to_string()
+    }
+}
+
+impl TryFrom<String> for FormatSpec {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
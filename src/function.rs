@@ -3,6 +3,7 @@ use std::{
     fmt,
     hash::{Hash, Hasher},
     ops::{Add, AddAssign},
+    sync::Arc,
 };
 
 use ecow::{EcoString, EcoVec};
@@ -14,7 +15,7 @@ use crate::{
     lex::CodeSpan,
     primitive::{ImplPrimitive, Primitive},
     value::Value,
-    Assembly, Ident,
+    Annotation, Assembly, FormatSpec, Ident,
 };
 
 /// A Uiua bytecode instruction
@@ -61,6 +62,7 @@ pub enum Instr {
     /// Do a format string
     Format {
         parts: EcoVec<EcoString>,
+        specs: EcoVec<FormatSpec>,
         span: usize,
     },
     /// Label an array
@@ -97,6 +99,11 @@ pub enum Instr {
         count: usize,
         span: usize,
     },
+    /// Check arguments against a binding's annotation
+    CheckAnnotation {
+        annotation: Arc<Annotation>,
+        span: usize,
+    },
     PushTemp {
         stack: TempStack,
         count: usize,
@@ -160,7 +167,18 @@ impl PartialEq for Instr {
             (Self::Prim(a, _), Self::Prim(b, _)) => a == b,
             (Self::ImplPrim(a, _), Self::ImplPrim(b, _)) => a == b,
             (Self::Call(a), Self::Call(b)) => a == b,
-            (Self::Format { parts: a, .. }, Self::Format { parts: b, .. }) => a == b,
+            (
+                Self::Format {
+                    parts: a,
+                    specs: a_specs,
+                    ..
+                },
+                Self::Format {
+                    parts: b,
+                    specs: b_specs,
+                    ..
+                },
+            ) => a == b && a_specs == b_specs,
             (Self::PushFunc(a), Self::PushFunc(b)) => a == b,
             (Self::PushTemp { count: a, .. }, Self::PushTemp { count: b, .. }) => a == b,
             (Self::PopTemp { count: a, .. }, Self::PopTemp { count: b, .. }) => a == b,
@@ -178,6 +196,10 @@ impl PartialEq for Instr {
             ) => ao == bo && ac == bc,
             (Self::DropTemp { count: a, .. }, Self::DropTemp { count: b, .. }) => a == b,
             (Self::TouchStack { count: a, .. }, Self::TouchStack { count: b, .. }) => a == b,
+            (
+                Self::CheckAnnotation { annotation: a, .. },
+                Self::CheckAnnotation { annotation: b, .. },
+            ) => a == b,
             _ => false,
         }
     }
@@ -239,11 +261,15 @@ impl fmt::Display for Instr {
             Instr::PushTempFunctions(count) => write!(f, "<push {count} functions>"),
             Instr::PopTempFunctions(count) => write!(f, "<pop {count} functions>"),
             Instr::GetTempFunction { offset, .. } => write!(f, "<get function at {offset}>"),
-            Instr::Format { parts, .. } => {
+            Instr::Format { parts, specs, .. } => {
                 write!(f, "$\"")?;
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
-                        write!(f, "_")?
+                        write!(f, "_")?;
+                        let spec = &specs[i - 1];
+                        if *spec != FormatSpec::default() {
+                            write!(f, "{{:{spec}}}")?;
+                        }
                     }
                     write!(f, "{part}")?
                 }
@@ -263,6 +289,7 @@ impl fmt::Display for Instr {
                 count, unbox: true, ..
             } => write!(f, "<unpack (unbox) {count}>"),
             Instr::TouchStack { count, .. } => write!(f, "<touch {count}>"),
+            Instr::CheckAnnotation { annotation, .. } => write!(f, "<check {annotation}>"),
             Instr::PushTemp { stack, count, .. } => write!(f, "<push {stack} {count}>"),
             Instr::PopTemp { stack, count, .. } => write!(f, "<pop {stack} {count}>"),
             Instr::CopyFromTemp {
@@ -11,7 +11,7 @@ use serde::*;
 use serde_tuple::*;
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{Inputs, Primitive};
+use crate::{FormatSpec, Inputs, Primitive};
 
 /// Lex a Uiua source file
 pub fn lex(
@@ -406,11 +406,12 @@ pub enum Token {
     Char(String),
     Str(String),
     Label(String),
-    FormatStr(Vec<String>),
-    MultilineString(Vec<String>),
+    FormatStr(FormatFragments),
+    MultilineString(FormatFragments),
     Simple(AsciiToken),
     Glyph(Primitive),
     LeftArrow,
+    LeftStrokeArrow,
     Newline,
     Spaces,
 }
@@ -428,13 +429,13 @@ impl Token {
             _ => None,
         }
     }
-    pub(crate) fn as_format_string(&self) -> Option<Vec<String>> {
+    pub(crate) fn as_format_string(&self) -> Option<FormatFragments> {
         match self {
             Token::FormatStr(frags) => Some(frags.clone()),
             _ => None,
         }
     }
-    pub(crate) fn as_multiline_string(&self) -> Option<Vec<String>> {
+    pub(crate) fn as_multiline_string(&self) -> Option<FormatFragments> {
         match self {
             Token::MultilineString(parts) => Some(parts.clone()),
             _ => None,
@@ -478,6 +479,7 @@ pub enum AsciiToken {
     TripleMinus,
     Quote,
     Quote2,
+    Tilde,
 }
 
 impl fmt::Display for AsciiToken {
@@ -503,6 +505,7 @@ impl fmt::Display for AsciiToken {
             AsciiToken::TripleMinus => write!(f, "---"),
             AsciiToken::Quote => write!(f, "'"),
             AsciiToken::Quote2 => write!(f, "''"),
+            AsciiToken::Tilde => write!(f, "~"),
         }
     }
 }
@@ -646,11 +649,14 @@ impl<'a> Lexer<'a> {
                 "*" => self.end(Star, start),
                 "%" => self.end(Percent, start),
                 "^" => self.end(Caret, start),
+                "=" if self.next_char_exact("~") => self.end(LeftStrokeArrow, start),
                 "=" => self.end(Equal, start),
                 "<" if self.next_char_exact("=") => self.end(LessEqual, start),
                 ">" if self.next_char_exact("=") => self.end(GreaterEqual, start),
                 "!" if self.next_char_exact("=") => self.end(BangEqual, start),
+                "~" => self.end(Tilde, start),
                 "←" => self.end(LeftArrow, start),
+                "↚" => self.end(LeftStrokeArrow, start),
                 // Comments
                 "#" => {
                     let mut n = 0;
@@ -989,21 +995,46 @@ impl<'a> Lexer<'a> {
     }
 }
 
-fn parse_format_fragments(s: &str) -> Vec<String> {
-    let mut frags: Vec<String> = Vec::new();
+/// The parts of a format string
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatFragments {
+    /// The text between the placeholders
+    pub frags: Vec<String>,
+    /// The specifier of each placeholder
+    pub specs: Vec<FormatSpec>,
+}
+
+fn parse_format_fragments(s: &str) -> FormatFragments {
+    let chars: Vec<char> = s.chars().collect();
+    let mut parts = FormatFragments::default();
     let mut curr = String::new();
-    for c in s.chars() {
-        match c {
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
             '_' => {
-                frags.push(curr);
+                parts.frags.push(curr);
                 curr = String::new();
+                // A specifier is written in braces, like `_{:.2}`
+                let spec = if chars[i + 1..].starts_with(&['{', ':']) {
+                    let (spec, len) = FormatSpec::parse(&chars[i + 3..]);
+                    (chars.get(i + 3 + len) == Some(&'}')).then_some((spec, len))
+                } else {
+                    None
+                };
+                if let Some((spec, len)) = spec {
+                    parts.specs.push(spec);
+                    i += len + 3;
+                } else {
+                    parts.specs.push(FormatSpec::default());
+                }
             }
             char::MAX => curr.push('_'),
             c => curr.push(c),
         }
+        i += 1;
     }
-    frags.push(curr);
-    frags
+    parts.frags.push(curr);
+    parts
 }
 
 /// Whether a character can be part of a Uiua identifier
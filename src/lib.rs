@@ -97,7 +97,11 @@ The `uiua` crate has the following noteable feature flags:
     - `regex`: Enables the `regex` function
     - `image`: Enables image encoding and decoding
     - `gif`: Enables GIF encoding and decoding
+    - `apng`: Enables animated PNG encoding
+    - `font`: Enables rendering text into images
+    - `plot`: Enables plotting data into images
     - `audio_encode`: Enables audio encoding and decoding
+    - `midi`: Enables MIDI encoding and decoding
 - `bytes`: Enables a byte array type. This type is semantically equivalent to a numeric array, but takes up less space. It is returned by certain file and network functions, as well as some comparison functions.
 - `native_sys`: Enables the [`NativeSys`] backend. This is the default backend used by the interpreter.
 - `audio`: Enables audio features in the [`NativeSys`] backend.
@@ -111,6 +115,7 @@ The `uiua` crate has the following noteable feature flags:
 #![warn(missing_docs)]
 
 mod algorithm;
+mod annotation;
 mod array;
 mod assembly;
 pub mod ast;
@@ -122,49 +127,70 @@ mod cowslice;
 mod error;
 mod ffi;
 pub mod format;
+mod format_spec;
 mod function;
 mod grid_fmt;
 mod lex;
 mod lsp;
 mod optimize;
+mod package;
 mod parse;
+#[cfg(feature = "plot")]
+mod plot;
 mod primitive;
 #[doc(hidden)]
 pub mod profile;
+mod purity;
 mod run;
 mod shape;
 #[cfg(feature = "stand")]
 #[doc(hidden)]
 pub mod stand;
+mod std_lib;
 mod sys;
 #[cfg(feature = "native_sys")]
 mod sys_native;
+mod sys_render;
+#[cfg(feature = "terminal_image")]
+mod term_image;
 mod value;
 
 #[allow(unused_imports)]
 pub use self::{
+    annotation::*,
     array::*,
     assembly::*,
     boxed::*,
     compile::*,
     error::*,
     ffi::*,
+    format_spec::*,
     function::*,
     lex::is_ident_char,
     lex::*,
     lsp::*,
     lsp::{spans, SpanKind},
+    package::*,
     parse::{parse, ParseError},
     primitive::*,
+    purity::*,
     run::*,
     shape::*,
+    std_lib::{STD_MODULES, STD_PREFIX},
     sys::*,
+    sys_render::*,
     value::*,
 };
 
 #[cfg(feature = "native_sys")]
 pub use self::sys_native::*;
 
+#[cfg(feature = "plot")]
+pub use self::plot::*;
+
+#[cfg(feature = "terminal_image")]
+pub use self::term_image::{image_protocol, set_image_protocol, ImageProtocol, IMAGE_PROTOCOL_VAR};
+
 pub use complex::*;
 use ecow::EcoString;
 
@@ -46,6 +46,27 @@ impl Spanner {
         for item in items {
             match item {
                 Item::TestScope(items) => spans.extend(self.items_spans(&items.value)),
+                Item::Module(module) => {
+                    spans.push(module.value.name.span.clone().sp(SpanKind::Ident));
+                    spans.extend(self.items_spans(&module.value.items));
+                }
+                Item::Record(record) => {
+                    spans.push(record.value.name.span.clone().sp(SpanKind::Ident));
+                    spans.push(record.value.tilde_span.clone().sp(SpanKind::Delimiter));
+                    for field in &record.value.fields {
+                        spans.push(field.span.clone().sp(SpanKind::Ident));
+                    }
+                }
+                Item::Variants(variants) => {
+                    spans.push(variants.value.name.span.clone().sp(SpanKind::Ident));
+                    spans.push(variants.value.tilde_span.clone().sp(SpanKind::Delimiter));
+                    for variant in &variants.value.variants {
+                        spans.push(variant.name.span.clone().sp(SpanKind::Ident));
+                        for field in &variant.fields {
+                            spans.push(field.span.clone().sp(SpanKind::Ident));
+                        }
+                    }
+                }
                 Item::Words(lines) => {
                     for line in lines {
                         spans.extend(self.words_spans(line))
@@ -181,7 +202,7 @@ pub use server::run_language_server;
 
 #[cfg(feature = "lsp")]
 mod server {
-    use std::sync::Arc;
+    use std::{path::Path, sync::Arc};
 
     use dashmap::DashMap;
     use tower_lsp::{
@@ -196,7 +217,7 @@ mod server {
         format::{format_str, FormatConfig},
         lex::Loc,
         primitive::{PrimClass, PrimDocFragment},
-        Assembly, BindingInfo, Compiler, PrimDocLine, Uiua,
+        Assembly, BindingInfo, Compiler, DiagnosticKind, Global, InputSrc, PrimDocLine, Span, Uiua,
     };
 
     pub struct LspDoc {
@@ -204,21 +225,69 @@ mod server {
         pub items: Vec<Item>,
         pub spans: Vec<Sp<SpanKind>>,
         pub asm: Assembly,
+        /// Names that can be completed, mapped to global indices
+        pub completions: Vec<(String, usize)>,
+        /// Diagnostics for this document
+        pub diagnostics: Vec<Diagnostic>,
     }
 
     impl LspDoc {
-        fn new(input: String) -> Self {
+        fn new(input: String, path: Option<&Path>) -> Self {
             let (items, _, _) = parse(&input, InputSrc::Str(0), &mut Inputs::default());
             let spanner = Spanner::new(&input);
             let spans = spanner.items_spans(&items);
             let compiler = &mut Compiler::new();
-            _ = compiler.load_str(&input);
+            // Compiling with the path resolves imports and checks for unused bindings.
+            // This only reads files. Git dependencies are never fetched, so only
+            // dependencies that have already been installed can be resolved.
+            let src = if let Some(path) = path {
+                _ = compiler.load_str_src(&input, path);
+                InputSrc::File(path.into())
+            } else {
+                _ = compiler.load_str(&input);
+                InputSrc::Str(0)
+            };
+            let diagnostics = (compiler.take_diagnostics().into_iter())
+                .filter_map(|diag| {
+                    let Span::Code(span) = &diag.span else {
+                        return None;
+                    };
+                    if span.src != src {
+                        return None;
+                    }
+                    Some(Diagnostic {
+                        range: uiua_span_to_lsp(span),
+                        severity: Some(match diag.kind {
+                            DiagnosticKind::Warning => DiagnosticSeverity::WARNING,
+                            DiagnosticKind::Advice => DiagnosticSeverity::INFORMATION,
+                            DiagnosticKind::Style => DiagnosticSeverity::HINT,
+                        }),
+                        source: Some("uiua".into()),
+                        message: diag.message,
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            let mut completions = Vec::new();
+            for (name, &index) in &compiler.scope.names {
+                completions.push((name.to_string(), index));
+                // Private module items are not accessible from here
+                if let Some(items) = compiler.module_items(index) {
+                    for (item, &item_index) in &items.names {
+                        if !items.private.contains(item) {
+                            completions.push((format!("{name} {item:?}"), item_index));
+                        }
+                    }
+                }
+            }
             let asm = compiler.finish();
             Self {
                 input,
                 items,
                 spans,
                 asm,
+                completions,
+                diagnostics,
             }
         }
     }
@@ -247,6 +316,18 @@ mod server {
         docs: DashMap<Url, LspDoc>,
     }
 
+    impl Backend {
+        async fn update_doc(&self, uri: Url, text: String) {
+            let path = uri.to_file_path().ok();
+            let doc = LspDoc::new(text, path.as_deref());
+            let diagnostics = doc.diagnostics.clone();
+            self.docs.insert(uri.clone(), doc);
+            self.client
+                .publish_diagnostics(uri, diagnostics, None)
+                .await;
+        }
+    }
+
     const STACK_FUNCTION_STT: SemanticTokenType = SemanticTokenType::new("stack_function");
     const NOADIC_FUNCTION_STT: SemanticTokenType = SemanticTokenType::new("noadic_function");
     const MONADIC_FUNCTION_STT: SemanticTokenType = SemanticTokenType::new("monadic_function");
@@ -300,6 +381,7 @@ mod server {
                     ),
                     rename_provider: Some(OneOf::Left(true)),
                     definition_provider: Some(OneOf::Left(true)),
+                    completion_provider: Some(CompletionOptions::default()),
                     ..Default::default()
                 },
                 ..Default::default()
@@ -313,17 +395,13 @@ mod server {
         }
 
         async fn did_open(&self, param: DidOpenTextDocumentParams) {
-            self.docs.insert(
-                param.text_document.uri,
-                LspDoc::new(param.text_document.text),
-            );
+            self.update_doc(param.text_document.uri, param.text_document.text)
+                .await;
         }
 
         async fn did_change(&self, params: DidChangeTextDocumentParams) {
-            self.docs.insert(
-                params.text_document.uri,
-                LspDoc::new(params.content_changes[0].text.clone()),
-            );
+            let text = params.content_changes[0].text.clone();
+            self.update_doc(params.text_document.uri, text).await;
         }
 
         async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
@@ -665,6 +743,39 @@ mod server {
             Ok(None)
         }
 
+        async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+            let doc = if let Some(doc) = self
+                .docs
+                .get(&params.text_document_position.text_document.uri)
+            {
+                doc
+            } else {
+                return Ok(None);
+            };
+            let items = (doc.completions.iter())
+                .map(|(name, index)| {
+                    let binding = &doc.asm.bindings[*index];
+                    let kind = match &binding.global {
+                        Global::Const(_) => CompletionItemKind::CONSTANT,
+                        Global::Func(_) | Global::Sig(_) => CompletionItemKind::FUNCTION,
+                        Global::Module { .. } | Global::ScopedModule { .. } => {
+                            CompletionItemKind::MODULE
+                        }
+                        Global::Variants { .. } => CompletionItemKind::ENUM,
+                    };
+                    CompletionItem {
+                        label: name.clone(),
+                        kind: Some(kind),
+                        detail: binding.global.signature().map(|sig| sig.to_string()),
+                        documentation: (binding.comment.as_ref())
+                            .map(|comment| Documentation::String(comment.to_string())),
+                        ..Default::default()
+                    }
+                })
+                .collect();
+            Ok(Some(CompletionResponse::Array(items)))
+        }
+
         async fn shutdown(&self) -> Result<()> {
             Ok(())
         }
@@ -707,4 +818,41 @@ mod server {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn private_completions() {
+            let doc = LspDoc::new(
+                "---Geometry\nSq ↚ ×.\nCircle ← ×π Sq\n---\nCube ↚ ×.×.\n".into(),
+                None,
+            );
+            let names: Vec<&str> = (doc.completions.iter())
+                .map(|(name, _)| name.as_str())
+                .collect();
+            assert!(names.contains(&"Geometry \"Circle\""), "{names:?}");
+            assert!(!names.contains(&"Geometry \"Sq\""), "{names:?}");
+            // Private bindings are still visible in their own file
+            assert!(names.contains(&"Cube"), "{names:?}");
+        }
+
+        #[test]
+        fn no_side_effects() {
+            let dir = std::env::temp_dir().join(format!("uiua-lsp-test-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("uiua.toml"),
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+                [dependencies]\nremote = { git = \"https://example.com/remote.git\" }\n",
+            )
+            .unwrap();
+            let input = "R ← &i \"remote\"\n";
+            LspDoc::new(input.into(), Some(&dir.join("main.ua")));
+            assert!(!dir.join(".uiua").exists());
+            assert!(!dir.join("uiua.lock").exists());
+            _ = std::fs::remove_dir_all(&dir);
+        }
+    }
 }
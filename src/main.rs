@@ -52,6 +52,13 @@ fn main() {
     }
 }
 
+/// Print diagnostics that were emitted at runtime
+fn print_diagnostics(rt: &mut Uiua) {
+    for diagnostic in rt.take_diagnostics() {
+        eprintln!("{}", diagnostic.report());
+    }
+}
+
 static WATCH_CHILD: Lazy<Mutex<Option<Child>>> = Lazy::new(Default::default);
 
 fn run() -> UiuaResult {
@@ -75,6 +82,14 @@ fn run() -> UiuaResult {
                     fs::write("main.ua", "\"Hello, World!\"").unwrap();
                 }
             }
+            App::Install => match uiua::Manifest::find_dir(Path::new(".")) {
+                Some(dir) => {
+                    if let Err(e) = uiua::install_dependencies(&dir) {
+                        eprintln!("Failed to install dependencies: {e}");
+                    }
+                }
+                None => eprintln!("No {} found", uiua::MANIFEST_FILE),
+            },
             App::Fmt {
                 path,
                 formatter_options,
@@ -99,6 +114,11 @@ fn run() -> UiuaResult {
                 mode,
                 #[cfg(feature = "audio")]
                 audio_options,
+                #[cfg(feature = "audio_encode")]
+                render_options,
+                #[cfg(feature = "terminal_image")]
+                image_options,
+                audio_input_options,
                 args,
             } => {
                 let path = if let Some(path) = path {
@@ -114,7 +134,31 @@ fn run() -> UiuaResult {
                 };
                 #[cfg(feature = "audio")]
                 setup_audio(audio_options);
-                let mut rt = Uiua::with_native_sys()
+                #[cfg(feature = "terminal_image")]
+                setup_image(image_options);
+                if let Some(input) = audio_input_options.audio_input {
+                    if let Err(e) = uiua::set_audio_input(
+                        &input,
+                        audio_input_options.audio_input_channels,
+                        audio_input_options.audio_input_rate,
+                    ) {
+                        eprintln!("Failed to open audio input: {e}");
+                        return Ok(());
+                    }
+                }
+                #[cfg(feature = "audio_encode")]
+                let mut rt = if render_options.render_audio.is_some() {
+                    Uiua::with_backend(uiua::AudioRenderSys::new(
+                        NativeSys,
+                        render_options.sample_rate,
+                        render_options.duration,
+                    ))
+                } else {
+                    Uiua::with_native_sys()
+                };
+                #[cfg(not(feature = "audio_encode"))]
+                let mut rt = Uiua::with_native_sys();
+                rt = rt
                     .with_file_path(&path)
                     .with_args(args)
                     .time_instrs(time_instrs);
@@ -133,7 +177,9 @@ fn run() -> UiuaResult {
                             return Ok(());
                         }
                     };
-                    rt.run_asm(assembly)?;
+                    let res = rt.run_asm(assembly);
+                    print_diagnostics(&mut rt);
+                    res?;
                 } else {
                     if !no_format {
                         let config = FormatConfig::from_source(
@@ -142,10 +188,28 @@ fn run() -> UiuaResult {
                         )?;
                         format_file(&path, &config, false)?;
                     }
+                    if let Err(e) = install_package_dependencies(&path) {
+                        eprintln!("Failed to install dependencies: {e}");
+                        return Ok(());
+                    }
                     let mode = mode.unwrap_or(RunMode::Normal);
-                    rt.compile_run(|comp| {
+                    let res = rt.compile_run(|comp| {
                         comp.mode(mode).print_diagnostics(true).load_file(&path)
-                    })?;
+                    });
+                    print_diagnostics(&mut rt);
+                    res?;
+                }
+                #[cfg(feature = "audio_encode")]
+                if let Some(audio_path) = render_options.render_audio {
+                    let render = rt.downcast_backend::<uiua::AudioRenderSys>().unwrap();
+                    match render.wav_bytes() {
+                        Ok(bytes) => {
+                            if let Err(e) = fs::write(audio_path, bytes) {
+                                eprintln!("Failed to write audio: {e}");
+                            }
+                        }
+                        Err(e) => eprintln!("Failed to encode audio: {e}"),
+                    }
                 }
                 print_stack(&rt.take_stack(), !no_color);
             }
@@ -161,6 +225,10 @@ fn run() -> UiuaResult {
                         }
                     }
                 };
+                if let Err(e) = install_package_dependencies(&path) {
+                    eprintln!("Failed to install dependencies: {e}");
+                    return Ok(());
+                }
                 let assembly = Compiler::with_backend(NativeSys)
                     .print_diagnostics(true)
                     .load_file(&path)?
@@ -182,16 +250,22 @@ fn run() -> UiuaResult {
                 no_color,
                 #[cfg(feature = "audio")]
                 audio_options,
+                #[cfg(feature = "terminal_image")]
+                image_options,
                 args,
             } => {
                 #[cfg(feature = "audio")]
                 setup_audio(audio_options);
+                #[cfg(feature = "terminal_image")]
+                setup_image(image_options);
                 let mut rt = Uiua::with_native_sys().with_args(args);
-                rt.compile_run(|comp| {
+                let res = rt.compile_run(|comp| {
                     comp.mode(RunMode::Normal)
                         .print_diagnostics(true)
                         .load_str(&code)
-                })?;
+                });
+                print_diagnostics(&mut rt);
+                res?;
                 print_stack(&rt.take_stack(), !no_color);
             }
             App::Test {
@@ -212,12 +286,18 @@ fn run() -> UiuaResult {
                 let config =
                     FormatConfig::from_source(formatter_options.format_config_source, Some(&path))?;
                 format_file(&path, &config, false)?;
+                if let Err(e) = install_package_dependencies(&path) {
+                    eprintln!("Failed to install dependencies: {e}");
+                    return Ok(());
+                }
                 let mut rt = Uiua::with_native_sys();
-                rt.compile_run(|comp| {
+                let res = rt.compile_run(|comp| {
                     comp.mode(RunMode::Test)
                         .print_diagnostics(true)
                         .load_file(path)
-                })?;
+                });
+                print_diagnostics(&mut rt);
+                res?;
                 println!("No failures!");
             }
             App::Watch {
@@ -227,7 +307,14 @@ fn run() -> UiuaResult {
                 clear,
                 args,
                 stdin_file,
+                #[cfg(feature = "terminal_image")]
+                image_options,
             } => {
+                // Runs inherit the protocol through the environment
+                #[cfg(feature = "terminal_image")]
+                if let Some(protocol) = image_options.image_protocol {
+                    env::set_var(uiua::IMAGE_PROTOCOL_VAR, protocol.to_string());
+                }
                 if let Err(e) = watch(
                     working_file_path().ok().as_deref(),
                     !no_format,
@@ -246,6 +333,8 @@ fn run() -> UiuaResult {
                 formatter_options,
                 #[cfg(feature = "audio")]
                 audio_options,
+                #[cfg(feature = "terminal_image")]
+                image_options,
                 args,
             } => {
                 let config = FormatConfig {
@@ -255,6 +344,8 @@ fn run() -> UiuaResult {
 
                 #[cfg(feature = "audio")]
                 setup_audio(audio_options);
+                #[cfg(feature = "terminal_image")]
+                setup_image(image_options);
                 let rt = Uiua::with_native_sys().with_args(args);
                 let mut compiler = Compiler::new();
                 compiler.mode(RunMode::Normal).print_diagnostics(true);
@@ -374,7 +465,19 @@ impl fmt::Display for NoWorkingFile {
     }
 }
 
+/// Install the dependencies of the package that contains a file, if there is one
+fn install_package_dependencies(path: &Path) -> Result<(), String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    match uiua::Manifest::find_dir(dir) {
+        Some(dir) => uiua::install_dependencies(&dir),
+        None => Ok(()),
+    }
+}
+
 fn working_file_path() -> Result<PathBuf, NoWorkingFile> {
+    if let Ok(manifest) = uiua::Manifest::from_dir(Path::new(".")) {
+        return Ok(manifest.package.entry);
+    }
     let main_in_src = PathBuf::from("src/main.ua");
     let main = if main_in_src.exists() {
         main_in_src
@@ -560,9 +663,19 @@ enum App {
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
+        #[cfg(feature = "audio_encode")]
+        #[clap(flatten)]
+        render_options: RenderOptions,
+        #[cfg(feature = "terminal_image")]
+        #[clap(flatten)]
+        image_options: ImageOptions,
+        #[clap(flatten)]
+        audio_input_options: AudioInputOptions,
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
+    #[clap(about = "Install the dependencies of the package in the current directory")]
+    Install,
     #[clap(about = "Build an assembly (the .uasm format is currently unstable)")]
     Build {
         path: Option<PathBuf>,
@@ -577,6 +690,9 @@ enum App {
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
+        #[cfg(feature = "terminal_image")]
+        #[clap(flatten)]
+        image_options: ImageOptions,
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
@@ -598,6 +714,9 @@ enum App {
         clear: bool,
         #[clap(long, help = "Read stdin from file")]
         stdin_file: Option<PathBuf>,
+        #[cfg(feature = "terminal_image")]
+        #[clap(flatten)]
+        image_options: ImageOptions,
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
@@ -617,6 +736,9 @@ enum App {
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
+        #[cfg(feature = "terminal_image")]
+        #[clap(flatten)]
+        image_options: ImageOptions,
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
@@ -663,6 +785,56 @@ struct AudioOptions {
     audio_port: Option<u16>,
 }
 
+#[cfg(feature = "audio_encode")]
+#[derive(clap::Args)]
+struct RenderOptions {
+    #[clap(long, help = "Render audio to a WAV file instead of playing it")]
+    render_audio: Option<PathBuf>,
+    #[clap(
+        long,
+        default_value_t = 10.0,
+        help = "The number of seconds to render each audio stream for"
+    )]
+    duration: f64,
+    #[clap(
+        long,
+        default_value_t = 44100,
+        help = "The sample rate of rendered audio"
+    )]
+    sample_rate: u32,
+}
+
+#[cfg(feature = "terminal_image")]
+#[derive(clap::Args)]
+struct ImageOptions {
+    #[clap(
+        long,
+        help = "The protocol for showing images in the terminal (one of auto, kitty, sixel, or halfblock)"
+    )]
+    image_protocol: Option<uiua::ImageProtocol>,
+}
+
+#[derive(clap::Args)]
+struct AudioInputOptions {
+    #[clap(
+        long,
+        help = "Read audio input from a WAV file, or from 16-bit little-endian PCM on stdin if `-`"
+    )]
+    audio_input: Option<String>,
+    #[clap(
+        long,
+        default_value_t = 1,
+        help = "The number of channels of PCM audio input"
+    )]
+    audio_input_channels: usize,
+    #[clap(
+        long,
+        default_value_t = 44100,
+        help = "The sample rate of PCM audio input"
+    )]
+    audio_input_rate: u32,
+}
+
 #[cfg(feature = "audio")]
 fn setup_audio(options: AudioOptions) {
     if let Some(time) = options.audio_time {
@@ -676,6 +848,13 @@ fn setup_audio(options: AudioOptions) {
     }
 }
 
+#[cfg(feature = "terminal_image")]
+fn setup_image(options: ImageOptions) {
+    if let Some(protocol) = options.image_protocol {
+        uiua::set_image_protocol(protocol);
+    }
+}
+
 fn uiua_files() -> Vec<PathBuf> {
     fs::read_dir(".")
         .unwrap()
@@ -851,6 +1030,7 @@ fn repl(mut rt: Uiua, mut compiler: Compiler, color: bool, config: FormatConfig)
         let res = compiler
             .load_str(&code)
             .and_then(|comp| rt.run_asm(comp.finish()));
+        print_diagnostics(rt);
         print_stack(&rt.take_stack(), color);
         match res {
             Ok(mut asm) => {
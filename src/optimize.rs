@@ -1,8 +1,8 @@
-use std::fmt;
+use std::{cell::RefCell, fmt};
 
 use ecow::EcoVec;
 
-use crate::{ImplPrimitive, Instr, Primitive};
+use crate::{Assembly, FuncSlice, ImplPrimitive, Instr, PrimClass, Primitive, Uiua};
 
 pub(crate) fn optimize_instrs_mut(instrs: &mut EcoVec<Instr>, mut new: Instr, maximal: bool) {
     use ImplPrimitive::*;
@@ -10,6 +10,12 @@ pub(crate) fn optimize_instrs_mut(instrs: &mut EcoVec<Instr>, mut new: Instr, ma
     if let Instr::Push(val) = &mut new {
         val.compress();
     }
+    // Constant folding
+    if let Instr::Prim(prim, _) = new {
+        if fold_constant(instrs, prim) {
+            return;
+        }
+    }
     match (instrs.make_mut(), new) {
         // Cosine
         ([.., Instr::Prim(Eta, _), Instr::Prim(Add, _)], Instr::Prim(Sin, span)) => {
@@ -143,6 +149,122 @@ pub(crate) fn optimize_instrs_mut(instrs: &mut EcoVec<Instr>, mut new: Instr, ma
     }
 }
 
+/// Whether a primitive can be evaluated at compile time if its arguments are known
+///
+/// Primitives are excluded if they are impure, if they can succeed with a different
+/// result when a fill value is set, or if their output can be much larger than their inputs.
+/// Dyadic pervasive primitives are further restricted by [`fold_constant`].
+fn is_foldable(prim: Primitive) -> bool {
+    use Primitive::*;
+    match prim.class() {
+        PrimClass::MonadicPervasive | PrimClass::DyadicPervasive => true,
+        _ => matches!(
+            prim,
+            Len | Shape
+                | First
+                | Reverse
+                | Deshape
+                | Fix
+                | Bits
+                | Transpose
+                | Rise
+                | Fall
+                | Classify
+                | Deduplicate
+                | Unique
+                | Box
+                | Match
+                | Couple
+                | Join
+                | Select
+                | Pick
+                | Take
+                | Drop
+                | Member
+                | IndexOf
+                | Type
+                | Utf
+        ),
+    }
+}
+
+/// Whether an instruction pushes a single constant value
+///
+/// Constant primitives are not folded themselves so that patterns like `○+η` can still be recognized.
+fn is_constant_instr(instr: &Instr) -> bool {
+    match instr {
+        Instr::Push(_) => true,
+        Instr::Prim(prim, _) => prim.class() == PrimClass::Constant,
+        _ => false,
+    }
+}
+
+/// Replace a foldable primitive whose arguments are all constants
+/// with its pushed outputs
+///
+/// Returns whether the primitive was folded.
+/// If evaluating the primitive fails, it is left to fail at runtime.
+fn fold_constant(instrs: &mut EcoVec<Instr>, prim: Primitive) -> bool {
+    if !is_foldable(prim) {
+        return false;
+    }
+    let Some(sig) = prim.signature() else {
+        return false;
+    };
+    if instrs.len() < sig.args || !(instrs[instrs.len() - sig.args..].iter()).all(is_constant_instr)
+    {
+        return false;
+    }
+    thread_local! {
+        /// The environment that folded primitives are evaluated in
+        static FOLD_ENV: RefCell<Uiua> = RefCell::new(Uiua::with_safe_sys());
+    }
+    // Dyadic pervasive primitives broadcast arguments whose shapes differ,
+    // but a fill value pads them instead, so only same-shaped or scalar
+    // arguments are folded
+    if prim.class() == PrimClass::DyadicPervasive {
+        let shapes: Vec<&[usize]> = (instrs[instrs.len() - sig.args..].iter())
+            .map(|instr| match instr {
+                Instr::Push(val) => val.shape().dims(),
+                _ => &[],
+            })
+            .collect();
+        if let [a, b] = shapes.as_slice() {
+            if a != b && !a.is_empty() && !b.is_empty() {
+                return false;
+            }
+        }
+    }
+    let mut args: EcoVec<Instr> = instrs[instrs.len() - sig.args..].iter().cloned().collect();
+    args.push(Instr::Prim(prim, 0));
+    let asm = Assembly {
+        top_slices: vec![FuncSlice {
+            start: 0,
+            len: args.len(),
+        }],
+        instrs: args,
+        ..Assembly::default()
+    };
+    let Some(values) = FOLD_ENV.with(|env| {
+        let mut env = env.borrow_mut();
+        let res = env.run_asm(asm);
+        let values = env.take_stack();
+        res.is_ok().then_some(values)
+    }) else {
+        return false;
+    };
+    if values.len() != sig.outputs {
+        return false;
+    }
+    for _ in 0..sig.args {
+        instrs.pop();
+    }
+    for value in values {
+        instrs.push(Instr::push(value));
+    }
+    true
+}
+
 pub(crate) fn optimize_instrs<I>(instrs: I, maximal: bool) -> EcoVec<Instr>
 where
     I: IntoIterator<Item = Instr> + fmt::Debug,
@@ -160,3 +282,42 @@ where
     // println!("to       {:?}", new);
     new
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Compiler, Instr, Primitive};
+
+    fn compile(code: &str) -> Vec<Instr> {
+        let asm = Compiler::new().load_str(code).unwrap().finish();
+        asm.top_slices
+            .iter()
+            .flat_map(|slice| &asm.instrs[slice.start..][..slice.len])
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn constant_folding() {
+        let instrs = compile("+3 4");
+        assert!(
+            matches!(instrs.as_slice(), [Instr::Push(val)] if val.to_string() == "7"),
+            "{instrs:?}"
+        );
+        // Impure and failing primitives are left to run
+        for (code, prim) in [
+            ("+1 ⚂", Primitive::Add),
+            ("+1 now", Primitive::Add),
+            ("⊢[]", Primitive::First),
+            ("+[1 2] [3 4 5]", Primitive::Add),
+            ("+[1_2_3] [4_5_6 7_8_9]", Primitive::Add),
+        ] {
+            let instrs = compile(code);
+            assert!(
+                instrs
+                    .iter()
+                    .any(|instr| matches!(instr, Instr::Prim(p, _) if *p == prim)),
+                "{code} was folded: {instrs:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,479 @@
+//! Package manifests and dependency resolution
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use serde::*;
+
+/// The name of a package manifest file
+pub const MANIFEST_FILE: &str = "uiua.toml";
+/// The name of a package lockfile
+pub const LOCK_FILE: &str = "uiua.lock";
+/// The directory, relative to a manifest, that git dependencies are checked out into
+pub const DEPS_DIR: &str = ".uiua/deps";
+
+/// A package manifest, read from a `uiua.toml` file
+///
+/// ```toml
+/// [package]
+/// name = "myapp"
+/// version = "0.1.0"
+/// entry = "main.ua"
+///
+/// [dependencies]
+/// mylib = { path = "../mylib" }
+/// other = { git = "https://github.com/someone/other", rev = "v1.0" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Information about the package itself
+    pub package: PackageInfo,
+    /// The package's dependencies, by the name they are imported with
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+/// Information about a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageInfo {
+    /// The name of the package
+    pub name: String,
+    /// The version of the package
+    pub version: String,
+    /// The file that is run or imported, relative to the manifest
+    #[serde(default = "default_entry")]
+    pub entry: PathBuf,
+}
+
+fn default_entry() -> PathBuf {
+    "main.ua".into()
+}
+
+/// A package dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Dependency {
+    /// A dependency on a local directory or file, relative to the manifest
+    Path {
+        /// The path to the dependency
+        path: PathBuf,
+    },
+    /// A dependency on a git repository
+    Git {
+        /// The URL or path of the repository
+        git: String,
+        /// The branch, tag, or commit to check out
+        ///
+        /// The default branch is used if this is not specified.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+    },
+}
+
+impl Manifest {
+    /// Read the manifest in a directory
+    pub fn from_dir(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_FILE);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let manifest: Self =
+            toml::from_str(&text).map_err(|e| format!("Invalid {}: {e}", path.display()))?;
+        for name in manifest.dependencies.keys() {
+            validate_name(name).map_err(|e| format!("Invalid {}: {e}", path.display()))?;
+        }
+        Ok(manifest)
+    }
+    /// Find the directory of the nearest manifest, starting in `dir` and searching its ancestors
+    pub fn find_dir(dir: &Path) -> Option<PathBuf> {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let dir = dir.canonicalize().ok()?;
+        dir.ancestors()
+            .find(|dir| dir.join(MANIFEST_FILE).is_file())
+            .map(Into::into)
+    }
+}
+
+/// A lockfile, which pins git dependencies to specific commits
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// The locked packages
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A git dependency pinned to a commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    /// The name of the dependency
+    pub name: String,
+    /// The URL or path of the repository
+    pub git: String,
+    /// The requested branch, tag, or commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    /// The commit that the dependency is pinned to
+    pub commit: String,
+}
+
+impl Lockfile {
+    /// Read the lockfile in a directory, or an empty one if it does not exist
+    pub fn from_dir(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(Lockfile::default());
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid {}: {e}", path.display()))
+    }
+    /// Write the lockfile to a directory
+    pub fn write_to_dir(&self, dir: &Path) -> Result<(), String> {
+        let path = dir.join(LOCK_FILE);
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(&path, text).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+}
+
+/// Check that a dependency name can be used as a directory name
+fn validate_name(name: &str) -> Result<(), String> {
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid dependency name `{name}`. \
+            Names may only contain letters, digits, and underscores."
+        ))
+    }
+}
+
+/// Get the local root of a dependency of the manifest in `manifest_dir`
+///
+/// This never runs git or touches the network.
+/// Git dependencies must already be installed with [`install_dependencies`].
+pub fn dependency_root(
+    manifest_dir: &Path,
+    name: &str,
+    dep: &Dependency,
+) -> Result<PathBuf, String> {
+    validate_name(name)?;
+    let root = match dep {
+        Dependency::Path { path } => manifest_dir.join(path),
+        Dependency::Git { .. } => {
+            let checkout = manifest_dir.join(DEPS_DIR).join(name);
+            if !checkout.join(".git").exists() {
+                return Err(format!(
+                    "Dependency `{name}` is not installed. \
+                    Run `uiua install` to install it."
+                ));
+            }
+            checkout
+        }
+    };
+    if root.exists() {
+        Ok(root)
+    } else {
+        Err(format!(
+            "Dependency `{name}` does not exist at {}",
+            root.display()
+        ))
+    }
+}
+
+/// Install the dependencies of the manifest in `manifest_dir` and of any packages it depends on
+///
+/// Git dependencies are checked out into the project's dependency cache and pinned in its lockfile.
+pub fn install_dependencies(manifest_dir: &Path) -> Result<(), String> {
+    install_dependencies_impl(manifest_dir, &mut HashSet::new())
+}
+
+fn install_dependencies_impl(
+    manifest_dir: &Path,
+    seen: &mut HashSet<PathBuf>,
+) -> Result<(), String> {
+    let canonical = manifest_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to read {}: {e}", manifest_dir.display()))?;
+    if !seen.insert(canonical) {
+        return Ok(());
+    }
+    let manifest = Manifest::from_dir(manifest_dir)?;
+    for (name, dep) in &manifest.dependencies {
+        if let Dependency::Git { git: url, rev } = dep {
+            install_git(manifest_dir, name, url, rev.as_deref())?;
+        }
+        let root = dependency_root(manifest_dir, name, dep)?;
+        if root.join(MANIFEST_FILE).is_file() {
+            install_dependencies_impl(&root, seen)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check out a git dependency at its locked commit, locking it if necessary
+fn install_git(
+    manifest_dir: &Path,
+    name: &str,
+    url: &str,
+    rev: Option<&str>,
+) -> Result<(), String> {
+    if url.starts_with('-') {
+        return Err(format!("Invalid git URL `{url}` for dependency `{name}`"));
+    }
+    if let Some(rev) = rev.filter(|rev| rev.starts_with('-')) {
+        return Err(format!("Invalid revision `{rev}` for dependency `{name}`"));
+    }
+    let checkout = manifest_dir.join(DEPS_DIR).join(name);
+    if !checkout.join(".git").exists() {
+        fs::create_dir_all(manifest_dir.join(DEPS_DIR))
+            .map_err(|e| format!("Failed to create dependency cache: {e}"))?;
+        let checkout = checkout.to_string_lossy();
+        git(manifest_dir, &["clone", "--quiet", "--", url, &checkout])?;
+    }
+    let mut lockfile = Lockfile::from_dir(manifest_dir)?;
+    let locked = (lockfile.packages.iter())
+        .find(|p| p.name == name && p.git == url && p.rev.as_deref() == rev)
+        .map(|p| p.commit.clone());
+    let commit = match locked {
+        Some(commit) => {
+            if commit.is_empty() || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!(
+                    "Invalid locked commit `{commit}` for dependency `{name}`"
+                ));
+            }
+            if git(
+                &checkout,
+                &["cat-file", "-e", &format!("{commit}^{{commit}}")],
+            )
+            .is_err()
+            {
+                git(&checkout, &["fetch", "--quiet", "origin"])?;
+            }
+            commit
+        }
+        None => {
+            git(&checkout, &["fetch", "--quiet", "origin"])?;
+            let commit = resolve_rev(&checkout, rev)
+                .map_err(|e| format!("Failed to resolve dependency `{name}`: {e}"))?;
+            lockfile.packages.retain(|p| p.name != name);
+            lockfile.packages.push(LockedPackage {
+                name: name.into(),
+                git: url.into(),
+                rev: rev.map(Into::into),
+                commit: commit.clone(),
+            });
+            lockfile.packages.sort_by(|a, b| a.name.cmp(&b.name));
+            lockfile.write_to_dir(manifest_dir)?;
+            commit
+        }
+    };
+    if git(&checkout, &["rev-parse", "HEAD"])? != commit {
+        git(&checkout, &["checkout", "--quiet", "--detach", &commit])?;
+    }
+    Ok(())
+}
+
+/// Resolve a branch, tag, or commit of a checkout to a commit hash
+fn resolve_rev(checkout: &Path, rev: Option<&str>) -> Result<String, String> {
+    let Some(rev) = rev else {
+        return git(checkout, &["rev-parse", "origin/HEAD^{commit}"]);
+    };
+    // Branches only exist as remote branches in a fresh clone
+    git(
+        checkout,
+        &["rev-parse", &format!("origin/{rev}^{{commit}}")],
+    )
+    .or_else(|_| git(checkout, &["rev-parse", &format!("{rev}^{{commit}}")]))
+    .map_err(|_| format!("unknown revision `{rev}`"))
+}
+
+/// Run a git command in a directory and get its trimmed output
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run git: {e}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().into())
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Resolve an import path through the nearest manifest to `dir`
+///
+/// The first component of the path is the name of a dependency.
+/// If it is the only component, the dependency's entry file is imported.
+/// Otherwise, the rest of the path is relative to the dependency's root.
+///
+/// Returns `None` if there is no manifest or the path does not name a dependency.
+pub(crate) fn resolve_package_import(
+    dir: &Path,
+    path: &Path,
+    mut dependency_root: impl FnMut(&Path, &str, &Dependency) -> Result<PathBuf, String>,
+) -> Option<Result<PathBuf, String>> {
+    let mut components = path.components();
+    let Some(Component::Normal(name)) = components.next() else {
+        return None;
+    };
+    let name = name.to_str()?;
+    let manifest_dir = Manifest::find_dir(dir)?;
+    let manifest = match Manifest::from_dir(&manifest_dir) {
+        Ok(manifest) => manifest,
+        Err(e) => return Some(Err(e)),
+    };
+    let dep = manifest.dependencies.get(name)?;
+    Some((|| {
+        let root = dependency_root(&manifest_dir, name, dep)?;
+        let rest = components.as_path();
+        Ok(if !rest.as_os_str().is_empty() {
+            root.join(rest)
+        } else if root.is_file() {
+            root
+        } else if root.join(MANIFEST_FILE).is_file() {
+            root.join(Manifest::from_dir(&root)?.package.entry)
+        } else {
+            root.join(default_entry())
+        })
+    })())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path, process::Command};
+
+    use super::*;
+    use crate::{Compiler, Uiua};
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn run(main: &Path) -> Vec<f64> {
+        let mut comp = Compiler::new();
+        comp.load_file(main)
+            .unwrap_or_else(|e| panic!("{}", e.report()));
+        let mut env = Uiua::with_safe_sys();
+        env.run_asm(comp.finish()).unwrap();
+        (env.take_stack().into_iter())
+            .map(|val| val.as_num(&env, "").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn dependencies() {
+        if Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let root = env::temp_dir().join(format!("uiua-package-test-{}", std::process::id()));
+        _ = fs::remove_dir_all(&root);
+        let (app, local, remote, bare) = (
+            root.join("app"),
+            root.join("local"),
+            root.join("remote"),
+            root.join("remote.git"),
+        );
+        for dir in [&app, &local, &remote] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        // A local dependency with a manifest
+        fs::write(
+            local.join("uiua.toml"),
+            "[package]\nname = \"local\"\nversion = \"0.1.0\"\nentry = \"lib.ua\"\n",
+        )
+        .unwrap();
+        fs::write(local.join("lib.ua"), "Five ← 5").unwrap();
+        // A git dependency in a bare repository
+        fs::write(remote.join("main.ua"), "Val ← 1").unwrap();
+        run_git(&remote, &["init", "--quiet"]);
+        run_git(&remote, &["add", "."]);
+        run_git(&remote, &["commit", "--quiet", "-m", "one"]);
+        run_git(
+            &root,
+            &["clone", "--quiet", "--bare", "remote", "remote.git"],
+        );
+        fs::write(
+            app.join("uiua.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+            [dependencies]\nlocal = { path = \"../local\" }\nremote = { git = \"../remote.git\" }\n",
+        )
+        .unwrap();
+        fs::write(
+            app.join("main.ua"),
+            "L ← &i \"local\"\nR ← &i \"remote\"\nFive ← L \"Five\"\nVal ← R \"Val\"\nFive\nVal",
+        )
+        .unwrap();
+        // Compiling does not install git dependencies
+        let Err(err) = Compiler::new().load_file(app.join("main.ua")) else {
+            panic!("uninstalled dependency compiled");
+        };
+        assert!(err.to_string().contains("not installed"), "{err}");
+        assert!(!app.join(".uiua").exists() && !app.join("uiua.lock").exists());
+        install_dependencies(&app).unwrap();
+        assert_eq!(run(&app.join("main.ua")), [5.0, 1.0]);
+        let lockfile = fs::read_to_string(app.join("uiua.lock")).unwrap();
+        assert!(lockfile.contains("name = \"remote\""));
+
+        // Updating the remote does not change the locked version
+        fs::write(remote.join("main.ua"), "Val ← 2").unwrap();
+        run_git(&remote, &["commit", "--quiet", "-am", "two"]);
+        run_git(
+            &remote,
+            &["push", "--quiet", &bare.to_string_lossy(), "HEAD"],
+        );
+        install_dependencies(&app).unwrap();
+        assert_eq!(run(&app.join("main.ua")), [5.0, 1.0]);
+
+        // Removing the lockfile picks up the new version
+        fs::remove_file(app.join("uiua.lock")).unwrap();
+        install_dependencies(&app).unwrap();
+        assert_eq!(run(&app.join("main.ua")), [5.0, 2.0]);
+
+        // Unknown dependencies are still treated as paths
+        fs::write(app.join("main.ua"), "M ← &i \"missing\"").unwrap();
+        assert!(Compiler::new().load_file(app.join("main.ua")).is_err());
+
+        // Names that are not identifiers and URLs that look like options are rejected
+        fs::write(
+            app.join("uiua.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+            [dependencies]\n\"../..\" = { path = \"../local\" }\n",
+        )
+        .unwrap();
+        assert!(install_dependencies(&app).is_err());
+        let dep = Dependency::Path {
+            path: "../local".into(),
+        };
+        assert!(dependency_root(&app, "../..", &dep).is_err());
+        fs::write(
+            app.join("uiua.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+            [dependencies]\nevil = { git = \"--upload-pack=touch pwned\" }\n",
+        )
+        .unwrap();
+        let err = install_dependencies(&app).unwrap_err();
+        assert!(err.contains("Invalid git URL"), "{err}");
+        assert!(!app.join("pwned").exists());
+
+        _ = fs::remove_dir_all(&root);
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     ast::*,
     function::{FunctionId, Signature},
     lex::{AsciiToken::*, Token::*, *},
-    Diagnostic, DiagnosticKind, Ident, Inputs, Primitive,
+    Annotation, ArgAnnotation, Diagnostic, DiagnosticKind, Dim, ElemType, Ident, Inputs, Primitive,
 };
 
 /// An error that occurred while parsing
@@ -296,6 +296,8 @@ impl<'i> Parser<'i> {
         self.try_spaces();
         Some(if let Some(binding) = self.try_binding() {
             Item::Binding(binding)
+        } else if let Some(item) = self.try_record() {
+            item
         } else {
             let lines = self.multiline_words();
             // Convert multiline words into multiple items
@@ -307,6 +309,15 @@ impl<'i> Parser<'i> {
                 Item::Words(lines)
             } else if parse_scopes {
                 let start = self.try_exact(TripleMinus)?;
+                // A name directly after the delimiter makes a module
+                let name_start = self.index;
+                let name = self.try_ident().filter(|_| {
+                    self.try_spaces();
+                    matches!(self.tokens.get(self.index), Some(t) if t.value == Newline)
+                });
+                if name.is_none() {
+                    self.index = name_start;
+                }
                 let items = self.items(false);
                 let span = if let Some(end) = self.try_exact(TripleMinus) {
                     start.merge(end)
@@ -314,7 +325,11 @@ impl<'i> Parser<'i> {
                     self.errors.push(self.expected([TripleMinus]));
                     start
                 };
-                Item::TestScope(span.sp(items))
+                if let Some(name) = name {
+                    Item::Module(span.sp(ScopedModule { name, items }))
+                } else {
+                    Item::TestScope(span.sp(items))
+                }
             } else {
                 return None;
             }
@@ -332,12 +347,19 @@ impl<'i> Parser<'i> {
         self.next_output_comment += 1;
         Some(n.span.sp(Word::OutputComment { i, n: n.value }))
     }
-    fn try_binding_init(&mut self) -> Option<(Sp<Ident>, CodeSpan)> {
+    fn try_binding_init(&mut self) -> Option<(Sp<Ident>, CodeSpan, bool)> {
         let start = self.index;
         let name = self.try_ident()?;
         // Left arrow
         let mut arrow_span = self.try_spaces().map(|w| w.span);
-        if let Some(span) = self.try_exact(Equal).or_else(|| self.try_exact(LeftArrow)) {
+        let mut public = true;
+        let arrow = (self.try_exact(Equal))
+            .or_else(|| self.try_exact(LeftArrow))
+            .or_else(|| {
+                public = false;
+                self.try_exact(LeftStrokeArrow)
+            });
+        if let Some(span) = arrow {
             arrow_span = Some(if let Some(arrow_span) = arrow_span {
                 arrow_span.merge(span)
             } else {
@@ -351,10 +373,10 @@ impl<'i> Parser<'i> {
         if let Some(span) = self.try_spaces().map(|w| w.span) {
             arrow_span = arrow_span.merge(span);
         }
-        Some((name, arrow_span))
+        Some((name, arrow_span, public))
     }
     fn try_binding(&mut self) -> Option<Binding> {
-        let (name, arrow_span) = self.try_binding_init()?;
+        let (name, arrow_span, public) = self.try_binding_init()?;
         // Check for invalid binding names
         if name.value.contains('&') {
             self.errors
@@ -374,6 +396,12 @@ impl<'i> Parser<'i> {
         }
         // Signature
         let signature = self.try_signature(Bar);
+        // Annotation
+        let annotation = if signature.is_some() {
+            self.try_annotation()
+        } else {
+            None
+        };
         // Words
         let words = self.try_words().unwrap_or_default();
         // Validate words
@@ -414,8 +442,61 @@ impl<'i> Parser<'i> {
         Some(Binding {
             name,
             arrow_span,
+            public,
             words,
             signature,
+            annotation,
+        })
+    }
+    fn try_record_init(&mut self) -> Option<(Sp<Ident>, CodeSpan)> {
+        let start = self.index;
+        let name = self.try_ident()?;
+        self.try_spaces();
+        let Some(tilde_span) = self.try_exact(Tilde) else {
+            self.index = start;
+            return None;
+        };
+        Some((name, tilde_span))
+    }
+    fn try_record(&mut self) -> Option<Item> {
+        let (name, tilde_span) = self.try_record_init()?;
+        // Variants are separated by bars
+        let mut groups = vec![Vec::new()];
+        let mut end = tilde_span.clone();
+        loop {
+            self.try_spaces();
+            if let Some(field) = self.try_ident() {
+                end = field.span.clone();
+                groups.last_mut().unwrap().push(field);
+            } else if let Some(bar) = self.try_exact(Bar) {
+                end = bar;
+                groups.push(Vec::new());
+            } else {
+                break;
+            }
+        }
+        let span = name.span.clone().merge(end);
+        Some(if groups.len() == 1 {
+            Item::Record(span.sp(Record {
+                name,
+                tilde_span,
+                fields: groups.pop().unwrap(),
+            }))
+        } else {
+            let mut variants = Vec::new();
+            for mut fields in groups {
+                if fields.is_empty() {
+                    self.expected_continue([Expectation::Term]);
+                    continue;
+                }
+                let name = fields.remove(0);
+                variants.push(VariantDecl { name, fields });
+            }
+            Item::Variants(span.sp(VariantType {
+                name,
+                tilde_span,
+                variants,
+            }))
         })
     }
     fn try_ident(&mut self) -> Option<Sp<Ident>> {
@@ -443,6 +524,86 @@ impl<'i> Parser<'i> {
         let span = start.merge(end);
         Some(span.sp(Signature::new(args, outs)))
     }
+    /// Try to parse argument annotations that follow a binding's signature
+    ///
+    /// The annotations are terminated by a `|`.
+    fn try_annotation(&mut self) -> Option<Sp<Annotation>> {
+        let start = self.index;
+        let mut args = Vec::new();
+        let mut span: Option<CodeSpan> = None;
+        loop {
+            if let Some(bar) = self.try_exact(Bar) {
+                if let Some(span) = span.filter(|_| !args.is_empty()) {
+                    self.try_spaces();
+                    return Some(span.merge(bar).sp(Annotation { args }));
+                }
+                break;
+            }
+            let Some(arg) = self.try_arg_annotation() else {
+                break;
+            };
+            span = Some(span.map_or(arg.span.clone(), |span| span.merge(arg.span)));
+            args.push(arg.value);
+            self.try_spaces();
+        }
+        self.index = start;
+        None
+    }
+    fn try_arg_annotation(&mut self) -> Option<Sp<ArgAnnotation>> {
+        let start = self.index;
+        let start_span = self.tokens.get(self.index)?.span.clone();
+        let ty = if let Some(ident) = self.try_ident() {
+            let Some(ty) = ElemType::from_name(&ident.value) else {
+                self.index = start;
+                return None;
+            };
+            Some(ty)
+        } else {
+            None
+        };
+        let shape = if self.try_exact(OpenBracket).is_some() {
+            let mut dims = Vec::new();
+            loop {
+                self.try_spaces();
+                if self.try_exact(CloseBracket).is_some() {
+                    break;
+                }
+                let dim = if let Some(n) = self.try_num() {
+                    let n = n.value.1;
+                    if n < 0.0 || n.fract() != 0.0 {
+                        self.index = start;
+                        return None;
+                    }
+                    Dim::Exact(n as usize)
+                } else if let Some(ident) = self.try_ident() {
+                    Dim::Named(ident.value)
+                } else if self.try_exact(Underscore).is_some() {
+                    Dim::Any
+                } else if self.try_exact(Primitive::Dup).is_some()
+                    && self.try_exact(Primitive::Dup).is_some()
+                {
+                    Dim::Rest
+                } else {
+                    self.index = start;
+                    return None;
+                };
+                dims.push(dim);
+            }
+            if dims.iter().filter(|dim| **dim == Dim::Rest).count() > 1 {
+                self.index = start;
+                return None;
+            }
+            Some(dims)
+        } else {
+            None
+        };
+        if ty.is_none() && shape.is_none() {
+            self.index = start;
+            return None;
+        }
+        let span = start_span.merge(self.prev_span());
+        Some(span.sp(ArgAnnotation { ty, shape }))
+    }
     fn sig_inner(&mut self) -> (usize, usize) {
         if let Some(sn) = self.try_num() {
             if let Some((a, o)) = sn.value.0.split_once('.') {
@@ -535,7 +696,7 @@ impl<'i> Parser<'i> {
         while self.try_spaces().is_some() {}
         loop {
             let curr = self.index;
-            if self.try_binding_init().is_some() {
+            if self.try_binding_init().is_some() || self.try_record_init().is_some() {
                 self.index = curr;
                 break;
             }
@@ -0,0 +1,382 @@
+//! Plotting numeric data into image arrays
+
+use std::str::FromStr;
+
+use crate::{Array, Shape, Value};
+
+/// The kind of a plot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlotKind {
+    /// Points connected by lines
+    #[default]
+    Line,
+    /// Unconnected points
+    Scatter,
+    /// Bars that extend from zero
+    Bar,
+}
+
+/// Options for plotting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlotOptions {
+    /// The kind of plot
+    pub kind: PlotKind,
+    /// The width of the image in pixels
+    pub width: usize,
+    /// The height of the image in pixels
+    pub height: usize,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            kind: PlotKind::default(),
+            width: 640,
+            height: 480,
+        }
+    }
+}
+
+const MIN_PLOT_SIZE: usize = 64;
+const MAX_PLOT_SIZE: usize = 8192;
+
+impl FromStr for PlotOptions {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = PlotOptions::default();
+        for option in s.split_whitespace() {
+            match option {
+                "line" => options.kind = PlotKind::Line,
+                "scatter" => options.kind = PlotKind::Scatter,
+                "bar" => options.kind = PlotKind::Bar,
+                size => {
+                    let (width, height) = size
+                        .split_once('x')
+                        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                        .ok_or_else(|| format!("Invalid plot option {option:?}"))?;
+                    let range = MIN_PLOT_SIZE..=MAX_PLOT_SIZE;
+                    if !range.contains(&width) || !range.contains(&height) {
+                        return Err(format!(
+                            "Plot dimensions must be between {MIN_PLOT_SIZE} and \
+                            {MAX_PLOT_SIZE}, but they are {width}x{height}"
+                        ));
+                    }
+                    options.width = width;
+                    options.height = height;
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A series of points to plot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlotSeries {
+    /// The x coordinates of the points
+    pub xs: Vec<f64>,
+    /// The y coordinates of the points
+    pub ys: Vec<f64>,
+}
+
+impl PlotSeries {
+    /// Get the series to plot from a value
+    ///
+    /// A rank 1 array is a single series of y values.
+    /// A rank 2 array is a list of series of y values.
+    /// In both cases, the x values are the indices.
+    /// A rank 3 array is a list of series, each of which is a row of x values and a row of y values.
+    pub fn from_value(value: &Value) -> Result<Vec<Self>, String> {
+        let data: Vec<f64> = match value {
+            Value::Num(nums) => nums.data.iter().copied().collect(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(bytes) => bytes.data.iter().map(|&b| b as f64).collect(),
+            _ => return Err("Plot data must be a numeric array".into()),
+        };
+        let indices = |n: usize| (0..n).map(|i| i as f64).collect();
+        Ok(match value.shape().dims() {
+            [n] => vec![PlotSeries {
+                xs: indices(*n),
+                ys: data,
+            }],
+            [_, n] => (data.chunks_exact((*n).max(1)))
+                .map(|ys| PlotSeries {
+                    xs: indices(*n),
+                    ys: ys.to_vec(),
+                })
+                .collect(),
+            [_, 2, n] => (data.chunks_exact((2 * *n).max(1)))
+                .map(|points| {
+                    let (xs, ys) = points.split_at(*n);
+                    PlotSeries {
+                        xs: xs.to_vec(),
+                        ys: ys.to_vec(),
+                    }
+                })
+                .collect(),
+            _ => {
+                return Err(format!(
+                    "Plot data must be a list, a table, or an array of \
+                    shape [n 2 m], but its shape is {}",
+                    value.shape()
+                ))
+            }
+        })
+    }
+}
+
+/// Colors of the series, which cycle
+const SERIES_COLORS: [[f64; 3]; 8] = [
+    [0.12, 0.47, 0.71],
+    [1.0, 0.5, 0.05],
+    [0.17, 0.63, 0.17],
+    [0.84, 0.15, 0.16],
+    [0.58, 0.4, 0.74],
+    [0.55, 0.34, 0.29],
+    [0.89, 0.47, 0.76],
+    [0.09, 0.75, 0.81],
+];
+const WHITE: [f64; 3] = [1.0; 3];
+const BLACK: [f64; 3] = [0.0; 3];
+const GRID: [f64; 3] = [0.9; 3];
+const LABEL_SIZE: f32 = 12.0;
+const TICK_LEN: f64 = 5.0;
+
+/// Plot series into an RGB image array
+pub fn plot(series: &[PlotSeries], options: PlotOptions) -> Array<f64> {
+    let (width, height) = (options.width, options.height);
+    let mut canvas = Canvas {
+        width,
+        height,
+        pixels: vec![WHITE; width * height],
+    };
+    let points = || {
+        (series.iter())
+            .flat_map(|s| s.xs.iter().zip(&s.ys))
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+    };
+    // Find the bounds of the data
+    let (mut x_min, mut x_max, mut y_min, mut y_max) = points().fold(
+        (
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(x_min, x_max, y_min, y_max), (&x, &y)| {
+            (x_min.min(x), x_max.max(x), y_min.min(y), y_max.max(y))
+        },
+    );
+    if x_min > x_max {
+        (x_min, x_max, y_min, y_max) = (0.0, 1.0, 0.0, 1.0);
+    }
+    // The distance between adjacent bars
+    let mut bar_slot = f64::INFINITY;
+    if options.kind == PlotKind::Bar {
+        let mut xs: Vec<f64> = points().map(|(&x, _)| x).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        xs.dedup();
+        for w in xs.windows(2) {
+            bar_slot = bar_slot.min(w[1] - w[0]);
+        }
+        if !bar_slot.is_finite() {
+            bar_slot = 1.0;
+        }
+        x_min -= bar_slot / 2.0;
+        x_max += bar_slot / 2.0;
+        y_min = y_min.min(0.0);
+        y_max = y_max.max(0.0);
+    }
+    for (min, max) in [(&mut x_min, &mut x_max), (&mut y_min, &mut y_max)] {
+        if *min == *max {
+            *min -= 0.5;
+            *max += 0.5;
+        } else if options.kind != PlotKind::Bar {
+            let pad = (*max - *min) * 0.05;
+            *min -= pad;
+            *max += pad;
+        }
+    }
+    if options.kind == PlotKind::Bar {
+        let pad = (y_max - y_min) * 0.05;
+        if y_max > 0.0 {
+            y_max += pad;
+        }
+        if y_min < 0.0 {
+            y_min -= pad;
+        }
+    }
+    // Lay out the axes
+    // Bars should not have ticks between them
+    let min_x_step = if options.kind == PlotKind::Bar {
+        bar_slot
+    } else {
+        0.0
+    };
+    let (x_step, x_ticks) = nice_ticks(x_min, x_max, (width / 80).clamp(2, 10), min_x_step);
+    let (y_step, y_ticks) = nice_ticks(y_min, y_max, (height / 60).clamp(2, 10), 0.0);
+    let x_labels: Vec<Array<f64>> = (x_ticks.iter()).map(|&x| tick_label(x, x_step)).collect();
+    let y_labels: Vec<Array<f64>> = (y_ticks.iter()).map(|&y| tick_label(y, y_step)).collect();
+    let label_width = |label: &Array<f64>| label.shape()[1] as f64;
+    let label_height = |label: &Array<f64>| label.shape()[0] as f64;
+    let left = y_labels.iter().map(label_width).fold(0.0, f64::max) + TICK_LEN + 8.0;
+    let right = (x_labels.last().map(label_width).unwrap_or(0.0) / 2.0 + 4.0).max(15.0);
+    let bottom = x_labels.iter().map(label_height).fold(0.0, f64::max) + TICK_LEN + 8.0;
+    let top = 10.0;
+    let (px0, px1) = (left.round(), (width as f64 - right).round());
+    let (py0, py1) = (top, (height as f64 - bottom).round());
+    let sx = |x: f64| px0 + (x - x_min) / (x_max - x_min) * (px1 - px0);
+    let sy = |y: f64| py1 - (y - y_min) / (y_max - y_min) * (py1 - py0);
+    // Draw the grid, ticks, and labels
+    for (&x, label) in x_ticks.iter().zip(&x_labels) {
+        let x = sx(x).floor();
+        canvas.fill_rect(x, py0, x + 1.0, py1, GRID);
+        canvas.fill_rect(x, py1, x + 1.0, py1 + TICK_LEN, BLACK);
+        canvas.draw_label(label, x + 0.5, py1 + TICK_LEN + 3.0, 0.5, 0.0);
+    }
+    for (&y, label) in y_ticks.iter().zip(&y_labels) {
+        let y = sy(y).floor();
+        canvas.fill_rect(px0, y, px1, y + 1.0, GRID);
+        canvas.fill_rect(px0 - TICK_LEN, y, px0, y + 1.0, BLACK);
+        canvas.draw_label(label, px0 - TICK_LEN - 3.0, y + 0.5, 1.0, 0.5);
+    }
+    // Draw the data
+    let series_count = series.len().max(1) as f64;
+    for (i, s) in series.iter().enumerate() {
+        let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+        let points = (s.xs.iter().zip(&s.ys)).map(|(&x, &y)| (x, y));
+        match options.kind {
+            PlotKind::Line => {
+                let mut prev: Option<(f64, f64)> = None;
+                let mut lonely = false;
+                for (x, y) in points {
+                    if !(x.is_finite() && y.is_finite()) {
+                        if let Some(p) = prev.filter(|_| lonely) {
+                            canvas.disc(p, 2.0, color);
+                        }
+                        prev = None;
+                        continue;
+                    }
+                    let p = (sx(x), sy(y));
+                    lonely = prev.is_none();
+                    if let Some(prev) = prev {
+                        canvas.line(prev, p, 2.0, color);
+                    }
+                    prev = Some(p);
+                }
+                if let Some(p) = prev.filter(|_| lonely) {
+                    canvas.disc(p, 2.0, color);
+                }
+            }
+            PlotKind::Scatter => {
+                for (x, y) in points.filter(|(x, y)| x.is_finite() && y.is_finite()) {
+                    canvas.disc((sx(x), sy(y)), 3.0, color);
+                }
+            }
+            PlotKind::Bar => {
+                let group_width = bar_slot * 0.8;
+                let bar_width = group_width / series_count;
+                for (x, y) in points.filter(|(x, y)| x.is_finite() && y.is_finite()) {
+                    let bar_left = x - group_width / 2.0 + i as f64 * bar_width;
+                    let (x0, x1) = (sx(bar_left), sx(bar_left + bar_width));
+                    let (y0, y1) = (sy(y.max(0.0)), sy(y.min(0.0)));
+                    canvas.fill_rect(x0.round(), y0.round(), x1.round(), y1.round(), color);
+                }
+            }
+        }
+    }
+    // Draw the axes over the data
+    if y_min < 0.0 && y_max > 0.0 && options.kind == PlotKind::Bar {
+        let y = sy(0.0).floor();
+        canvas.fill_rect(px0, y, px1, y + 1.0, BLACK);
+    }
+    canvas.fill_rect(px0 - 1.0, py0, px0, py1 + 1.0, BLACK);
+    canvas.fill_rect(px0 - 1.0, py1, px1, py1 + 1.0, BLACK);
+    let shape = Shape::from_iter([height, width, 3]);
+    let data: Vec<f64> = canvas.pixels.into_iter().flatten().collect();
+    Array::new(shape, data.as_slice())
+}
+
+/// Choose evenly spaced round numbers between two bounds
+fn nice_ticks(min: f64, max: f64, target: usize, min_step: f64) -> (f64, Vec<f64>) {
+    let rough = ((max - min) / target as f64).max(min_step);
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let step = magnitude
+        * match rough / magnitude {
+            n if n < 1.5 => 1.0,
+            n if n < 3.0 => 2.0,
+            n if n < 7.0 => 5.0,
+            _ => 10.0,
+        };
+    let start = (min / step).ceil() as i64;
+    let end = (max / step).floor() as i64;
+    let ticks = (start..=end).map(|i| i as f64 * step).collect();
+    (step, ticks)
+}
+
+fn tick_label(n: f64, step: f64) -> Array<f64> {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    let n = if n.abs() < step * 1e-6 { 0.0 } else { n };
+    let text = format!("{n:.decimals$}").replace('-', "¯");
+    crate::rasterize_text(crate::builtin_font(crate::BUILTIN_FONT).unwrap(), LABEL_SIZE, &text)
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f64; 3]>,
+}
+
+impl Canvas {
+    fn blend(&mut self, x: i64, y: i64, color: [f64; 3], alpha: f64) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 || alpha <= 0.0 {
+            return;
+        }
+        let alpha = alpha.min(1.0);
+        let pixel = &mut self.pixels[y as usize * self.width + x as usize];
+        for (p, c) in pixel.iter_mut().zip(color) {
+            *p += (c - *p) * alpha;
+        }
+    }
+    fn fill_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, color: [f64; 3]) {
+        for y in y0 as i64..y1 as i64 {
+            for x in x0 as i64..x1 as i64 {
+                self.blend(x, y, color, 1.0);
+            }
+        }
+    }
+    /// Draw an antialiased line
+    fn line(&mut self, a: (f64, f64), b: (f64, f64), width: f64, color: [f64; 3]) {
+        let r = width / 2.0;
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len_sq = dx * dx + dy * dy;
+        let x_range = (a.0.min(b.0) - r - 1.0).floor() as i64..=(a.0.max(b.0) + r + 1.0) as i64;
+        let y_range = (a.1.min(b.1) - r - 1.0).floor() as i64..=(a.1.max(b.1) + r + 1.0) as i64;
+        for y in y_range {
+            for x in x_range.clone() {
+                let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+                let t = if len_sq == 0.0 {
+                    0.0
+                } else {
+                    (((cx - a.0) * dx + (cy - a.1) * dy) / len_sq).clamp(0.0, 1.0)
+                };
+                let dist = (cx - a.0 - t * dx).hypot(cy - a.1 - t * dy);
+                self.blend(x, y, color, r + 0.5 - dist);
+            }
+        }
+    }
+    /// Draw an antialiased filled circle
+    fn disc(&mut self, center: (f64, f64), r: f64, color: [f64; 3]) {
+        self.line(center, center, 2.0 * r, color);
+    }
+    /// Draw a label, aligned by fractions of its size
+    fn draw_label(&mut self, label: &Array<f64>, x: f64, y: f64, align_x: f64, align_y: f64) {
+        let (h, w) = (label.shape()[0], label.shape()[1]);
+        let left = (x - w as f64 * align_x).round() as i64;
+        let top = (y - h as f64 * align_y).round() as i64;
+        for (i, &coverage) in label.data.iter().enumerate() {
+            let (lx, ly) = ((i % w) as i64, (i / w) as i64);
+            self.blend(left + lx, top + ly, BLACK, coverage);
+        }
+    }
+}
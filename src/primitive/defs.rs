@@ -726,6 +726,16 @@ primitive!(
     /// ex: ⋕ "1/2"
     /// ex! ⋕ "dog"
     ///
+    /// Scientific notation, thousands separators, and hexadecimal, binary, and octal prefixes are also accepted.
+    /// ex: ⋕ "1.5e3"
+    /// ex: ⋕ "1,234,567"
+    /// ex: ⋕ "0xff"
+    /// ex: ⋕ "0b1010"
+    /// Strings that look like complex numbers parse into complex numbers.
+    /// ex: ⋕ "3+4i"
+    ///
+    /// To parse integers in other bases, use [radix].
+    ///
     /// [parse] is semi-pervasive. It works on multidimensional arrays of characters or boxes.
     /// ex: ⋕ {"5" "24" "106"}
     /// ex: ⋕ .↯3_4 "012"
@@ -736,6 +746,7 @@ primitive!(
     /// [un][parse] on a non-scalar number array will [box] each string.
     /// ex: °⋕ 1_2_3
     /// ex: °⋕ ↯3_4⇡12
+    /// To format integers in other bases, use [radix].
     (1, Parse, Misc, ("parse", '⋕')),
     /// Check if two arrays are exactly the same
     ///
@@ -1797,6 +1808,91 @@ primitive!(
     /// ex: -@\0 "👩🏽‍👩🏻‍👦🏻‍👧🏽"
     /// ex: utf "👩🏽‍👩🏻‍👦🏻‍👧🏽"
     (1, Utf, Misc, "utf"),
+    /// Split a string into its grapheme clusters
+    ///
+    /// A grapheme cluster is what a reader would consider a single character.
+    /// Characters in Uiua are single Unicode codepoints, so some graphemes are made of multiple characters.
+    /// ex: graphemes "e\u{301}ab"
+    /// ex: ⧻ "👩🏽‍👩🏻‍👦🏻‍👧🏽"
+    ///   : ⧻ graphemes "👩🏽‍👩🏻‍👦🏻‍👧🏽"
+    /// You can use [un] to join the graphemes back into a string.
+    /// ex: °graphemes {"a" "b\u{301}" "c"}
+    /// ex: ⍜graphemes⇌ "noe\u{308}l"
+    (1, Graphemes, Misc, "graphemes"),
+    /// Convert a string to uppercase
+    ///
+    /// ex: upper "Hello, World!"
+    /// The conversion follows the full Unicode case mapping rules, so the result may be longer than the input.
+    /// ex: upper "straße"
+    /// Arrays of rank greater than `1` are converted character by character, so their shape is preserved.
+    /// ex: upper ["abc" "ßef"]
+    /// Box arrays have each of their strings converted.
+    /// ex: upper {"hello" "there"}
+    ///
+    /// [under][upper] restores the original case of characters that were not changed.
+    /// ex: ⍜upper(⍜⊢(+1)) "hello"
+    (1, Upper, Misc, "upper"),
+    /// Convert a string to lowercase
+    ///
+    /// ex: lower "Hello, World!"
+    /// ex: lower "ΣΊΣΥΦΟΣ"
+    /// Arrays of rank greater than `1` are converted character by character, so their shape is preserved.
+    /// Box arrays have each of their strings converted.
+    /// ex: lower {"HELLO" "There"}
+    ///
+    /// [under][lower] restores the original case of characters that were not changed.
+    /// ex: ⍜lower(⍜⊢(+1)) "HeLLo WorLd"
+    (1, Lower, Misc, "lower"),
+    /// Convert a string to title case
+    ///
+    /// The first letter of each word is converted to titlecase, and the rest of the word is converted to lowercase.
+    /// ex: title "the QUICK brown fox"
+    /// ex: title "ǆungla"
+    /// Box arrays have each of their strings converted.
+    /// ex: title {"hello" "wORLD"}
+    ///
+    /// [under][title] restores the original case of characters that were not changed.
+    /// ex: ⍜title(⊂:"!") "hELLO"
+    (1, Title, Misc, "title"),
+    /// Normalize a string to Unicode Normalization Form C
+    ///
+    /// NFC composes characters with combining marks into single characters where possible.
+    /// ex: ⧻ "e\u{301}"
+    ///   : ⧻ nfc "e\u{301}"
+    /// Box arrays have each of their strings normalized.
+    /// ex: ∵(⧻°□) nfc {"é" "e\u{301}"}
+    (1, Nfc, Misc, "nfc"),
+    /// Normalize a string to Unicode Normalization Form D
+    ///
+    /// NFD decomposes characters into base characters followed by combining marks.
+    /// ex: ⧻ "é"
+    ///   : ⧻ nfd "é"
+    /// Box arrays have each of their strings normalized.
+    /// ex: ∵(⧻°□) nfd {"ä" "ö" "u"}
+    (1, Nfd, Misc, "nfd"),
+    /// Get the display width of a string
+    ///
+    /// This is the number of columns the string takes up in a monospace terminal.
+    /// Wide characters like CJK ideographs and most emoji take up 2 columns, and combining marks take up none.
+    /// ex: textwidth "hello"
+    /// ex: textwidth "日本語"
+    /// ex: [⊃⧻ textwidth] "e\u{301}"
+    /// The width of each row of a higher-rank character array is measured separately.
+    /// ex: textwidth ["ab" "日本"]
+    /// Box arrays have the width of each of their strings measured.
+    /// ex: textwidth {"a" "ab" "あ"}
+    (1, TextWidth, Misc, "textwidth"),
+    /// Convert integers to or from strings in a base
+    ///
+    /// The first argument is the base, which must be between 2 and 36.
+    /// Numbers are formatted as strings.
+    /// ex: radix 16 255
+    /// ex: radix 2 [5 6 7]
+    /// Strings are parsed as integers.
+    /// ex: radix 16 "ff"
+    /// ex: radix 2 {"101" "¯11"}
+    /// ex! radix 16 1.5
+    (2, Radix, Misc, "radix"),
     /// Generate a unique tag
     ///
     /// Tags are just numbers and are unique across multiple threads, but not across multiple runs.
@@ -2054,6 +2150,7 @@ impl_primitive!(
     (1, InvWhere),
     (1(2), InvCouple),
     (1, InvUtf),
+    (1, InvGraphemes),
     (1(2), InvAtan),
     (1(2), InvComplex),
     (1, InvParse),
@@ -2078,6 +2175,9 @@ impl_primitive!(
     (3(2), Unjoin),
     (3[1], Unpartition),
     (3[1], Ungroup),
+    (2, RestoreUpper),
+    (2, RestoreLower),
+    (2, RestoreTitle),
     // Optimizations
     (1, Cos),
     (1, Last),
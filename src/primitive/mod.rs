@@ -137,6 +137,7 @@ impl fmt::Display for ImplPrimitive {
             InvAtan => write!(f, "{Un}{Atan}"),
             InvComplex => write!(f, "{Un}{Complex}"),
             InvUtf => write!(f, "{Un}{Utf}"),
+            InvGraphemes => write!(f, "{Un}{Graphemes}"),
             InvParse => write!(f, "{Un}{Parse}"),
             InvFix => write!(f, "{Un}{Fix}"),
             InvScan => write!(f, "{Un}{Scan}"),
@@ -159,6 +160,9 @@ impl fmt::Display for ImplPrimitive {
             Unrerank => write!(f, "{Un}{Rerank}"),
             Unreshape => write!(f, "{Un}{Reshape}"),
             Ungroup => write!(f, "{Un}{Group}"),
+            RestoreUpper => write!(f, "{Un}{Upper}"),
+            RestoreLower => write!(f, "{Un}{Lower}"),
+            RestoreTitle => write!(f, "{Un}{Title}"),
             Unjoin => write!(f, "{Un}{Join}"),
             FirstMinIndex => write!(f, "{First}{Rise}"),
             FirstMaxIndex => write!(f, "{First}{Fall}"),
@@ -483,6 +487,14 @@ impl Primitive {
             }
             Primitive::Parse => env.monadic_ref_env(Value::parse_num)?,
             Primitive::Utf => env.monadic_ref_env(Value::utf8)?,
+            Primitive::Graphemes => env.monadic_ref_env(Value::graphemes)?,
+            Primitive::Upper => env.monadic_env(Value::upper)?,
+            Primitive::Lower => env.monadic_env(Value::lower)?,
+            Primitive::Title => env.monadic_env(Value::title)?,
+            Primitive::Nfc => env.monadic_ref_env(Value::nfc)?,
+            Primitive::Nfd => env.monadic_ref_env(Value::nfd)?,
+            Primitive::TextWidth => env.monadic_ref_env(Value::text_width)?,
+            Primitive::Radix => env.dyadic_rr_env(Value::radix)?,
             Primitive::Range => env.monadic_ref_env(Value::range)?,
             Primitive::Reverse => env.monadic_mut(Value::reverse)?,
             Primitive::Deshape => env.monadic_mut(Value::deshape)?,
@@ -832,6 +844,14 @@ impl ImplPrimitive {
             }
             ImplPrimitive::InvWhere => env.monadic_ref_env(Value::inverse_where)?,
             ImplPrimitive::InvUtf => env.monadic_ref_env(Value::inv_utf8)?,
+            ImplPrimitive::InvGraphemes => env.monadic_ref_env(Value::inv_graphemes)?,
+            ImplPrimitive::RestoreUpper
+            | ImplPrimitive::RestoreLower
+            | ImplPrimitive::RestoreTitle => {
+                let orig = env.pop(1)?;
+                let changed = env.pop(2)?;
+                env.push(changed.restore_case(&orig));
+            }
             ImplPrimitive::InverseBits => env.monadic_ref_env(Value::inv_bits)?,
             ImplPrimitive::Unpartition => loops::unpartition(env)?,
             ImplPrimitive::Ungroup => loops::ungroup(env)?,
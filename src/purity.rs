@@ -0,0 +1,140 @@
+//! Analysis of the side effects of functions
+
+use serde::*;
+
+use crate::{Assembly, Function, Global, ImplPrimitive, Instr, Primitive};
+
+/// The kinds of side effects a function may have
+///
+/// The variants are ordered from least to most impure.
+/// A function with several kinds of effects is classified by the most impure one.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Purity {
+    /// The function always returns the same outputs for the same inputs
+    #[default]
+    Pure,
+    /// The function generates random or otherwise unique values
+    Random,
+    /// The function depends on the current time
+    Time,
+    /// The function performs I/O, such as system functions, printing, or thread communication
+    Io,
+}
+
+impl Purity {
+    /// Whether this is [`Purity::Pure`]
+    pub fn is_pure(&self) -> bool {
+        *self == Purity::Pure
+    }
+    /// Get the purity of a primitive, not including any functions passed to it
+    pub fn of_prim(prim: Primitive) -> Self {
+        use Primitive::*;
+        match prim {
+            Rand | Tag => Purity::Random,
+            Now => Purity::Time,
+            Sys(_) | Trace | Stack | Dump | Spawn | Wait | Send | Recv | TryRecv => Purity::Io,
+            // Recursion reruns the function it is in, so it adds no effects of its own
+            Recur => Purity::Pure,
+            _ => Purity::Pure,
+        }
+    }
+    /// Get the purity of an implementation primitive
+    pub(crate) fn of_impl_prim(prim: ImplPrimitive) -> Self {
+        use ImplPrimitive::*;
+        match prim {
+            ReplaceRand | ReplaceRand2 => Purity::Random,
+            InvTrace | InvStack | InvDump => Purity::Io,
+            _ => Purity::Pure,
+        }
+    }
+}
+
+/// Get the purity of some instructions, including the functions they push
+///
+/// Functions passed to custom modifiers are accounted for where they are pushed,
+/// so getting a modifier's function argument is considered pure.
+///
+/// A binding's name is not in scope in its own body, so functions can only recurse with [`Primitive::Recur`].
+pub(crate) fn instrs_purity(instrs: &[Instr], asm: &Assembly) -> Purity {
+    let mut purity = Purity::Pure;
+    for instr in instrs {
+        let instr_purity = match instr {
+            Instr::Prim(prim, _) => Purity::of_prim(*prim),
+            Instr::ImplPrim(prim, _) => Purity::of_impl_prim(*prim),
+            Instr::PushFunc(f) => asm.function_purity(f),
+            Instr::CallGlobal { index, sig, .. } => match &asm.bindings[*index].global {
+                Global::Func(f) => asm.function_purity(f),
+                // Globals bound at runtime are only known to be pure if they are constants
+                Global::Sig(_) if sig.args == 0 => Purity::Pure,
+                Global::Const(_) => Purity::Pure,
+                _ => Purity::Io,
+            },
+            // Functions defined in Rust cannot be analyzed
+            Instr::Dynamic(_) => Purity::Io,
+            _ => Purity::Pure,
+        };
+        purity = purity.max(instr_purity);
+        if purity == Purity::Io {
+            break;
+        }
+    }
+    purity
+}
+
+impl Assembly {
+    /// Get the purity of a function
+    ///
+    /// This uses the purity recorded when the function was compiled if there is one.
+    pub fn function_purity(&self, f: &Function) -> Purity {
+        (self.purity(&f.id)).unwrap_or_else(|| instrs_purity(f.instrs(self), self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Compiler, FunctionId};
+
+    #[test]
+    fn function_purity() {
+        let mut comp = Compiler::new();
+        comp.load_str(
+            "\
+# Experimental!
+Pure ← +1
+Random ← +⚂
+Time ← ⊂now
+Io ← &p
+Nested ← ≡(+Random)
+Fact ← ↬((|1 ×↫-1.|1)<2.)
+Countdown ← ↬((|1 ↫-1&p.|0)<1.)",
+        )
+        .unwrap();
+        let asm = comp.finish();
+        let purity = |asm: &Assembly, name: &str| asm.purity(&FunctionId::Named(name.into()));
+        assert_eq!(purity(&asm, "Pure"), Some(Purity::Pure));
+        assert_eq!(purity(&asm, "Random"), Some(Purity::Random));
+        assert_eq!(purity(&asm, "Time"), Some(Purity::Time));
+        assert_eq!(purity(&asm, "Io"), Some(Purity::Io));
+        assert_eq!(purity(&asm, "Nested"), Some(Purity::Random));
+        assert_eq!(purity(&asm, "Fact"), Some(Purity::Pure));
+        assert_eq!(purity(&asm, "Countdown"), Some(Purity::Io));
+
+        // Purity survives serialization
+        let json = serde_json::to_string(&asm).unwrap();
+        let asm: Assembly = serde_json::from_str(&json).unwrap();
+        assert_eq!(purity(&asm, "Random"), Some(Purity::Random));
+        assert_eq!(purity(&asm, "Io"), Some(Purity::Io));
+    }
+
+    #[test]
+    fn shared_ids() {
+        // Functions with the same id share the most impure purity
+        let mut comp = Compiler::new();
+        comp.load_str("F ← &p\nF ← +1").unwrap();
+        let asm = comp.finish();
+        assert_eq!(asm.purity(&FunctionId::Named("F".into())), Some(Purity::Io));
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     cell::RefCell,
     cmp::Ordering,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fmt,
     hash::Hash,
     mem::{replace, size_of, take},
@@ -17,9 +17,10 @@ use instant::Duration;
 use thread_local::ThreadLocal;
 
 use crate::{
-    algorithm, array::Array, boxed::Boxed, check::instrs_temp_signatures, function::*, lex::Span,
-    value::Value, Assembly, Compiler, Complex, Global, Ident, Inputs, IntoSysBackend, Primitive,
-    SafeSys, SysBackend, SysOp, TraceFrame, UiuaError, UiuaResult,
+    algorithm, annotation::ArgInfo, array::Array, boxed::Boxed, check::instrs_temp_signatures,
+    function::*, lex::Span, value::Value, Annotation, Assembly, Compiler, Complex, Diagnostic,
+    DiagnosticKind, FormatSpec, Global, Ident, Inputs, IntoSysBackend, Primitive, SafeSys,
+    SysBackend, SysOp, TraceFrame, UiuaError, UiuaResult,
 };
 
 /// The Uiua interpreter
@@ -71,6 +72,8 @@ pub(crate) struct Runtime {
     thread: ThisThread,
     /// Values for output comments
     pub(crate) output_comments: HashMap<usize, Vec<Vec<Value>>>,
+    /// Diagnostics from checking annotations
+    pub(crate) diagnostics: BTreeSet<Diagnostic>,
     /// Memoized values
     pub(crate) memo: Arc<ThreadLocal<RefCell<MemoMap>>>,
 }
@@ -196,6 +199,7 @@ impl Default for Runtime {
             execution_start: 0.0,
             thread: ThisThread::default(),
             output_comments: HashMap::new(),
+            diagnostics: BTreeSet::new(),
             memo: Arc::new(ThreadLocal::new()),
         }
     }
@@ -322,6 +326,7 @@ impl Uiua {
                     execution_limit: env.rt.execution_limit,
                     time_instrs: env.rt.time_instrs,
                     output_comments: env.rt.output_comments.clone(),
+                    diagnostics: take(&mut env.rt.diagnostics),
                     ..Runtime::default()
                 };
             }
@@ -440,10 +445,14 @@ code:
                             "Signature global was not overwritten. \
                             This is a bug in the interpreter.",
                         )),
-                        Global::Module { .. } => Err(self.error(
+                        Global::Module { .. } | Global::ScopedModule { .. } => Err(self.error(
                             "Called module global. \
                             This is a bug in the interpreter.",
                         )),
+                        Global::Variants { .. } => Err(self.error(
+                            "Called variant type global. \
+                            This is a bug in the interpreter.",
+                        )),
                     }
                 }
                 &Instr::BindGlobal { span, index } => {
@@ -564,13 +573,20 @@ code:
                     env.rt.function_stack.push(f.clone());
                     Ok(())
                 }),
-                Instr::Format { parts, span } => {
+                Instr::Format { parts, specs, span } => {
                     let parts = parts.clone();
+                    let specs = specs.clone();
                     self.with_span(*span, |env| {
                         let mut s = String::new();
                         for (i, part) in parts.into_iter().enumerate() {
                             if i > 0 {
-                                s.push_str(&env.pop(("format argument", i))?.format());
+                                let val = env.pop(("format argument", i))?;
+                                let spec = &specs[i - 1];
+                                if *spec == FormatSpec::default() {
+                                    s.push_str(&val.format());
+                                } else {
+                                    s.push_str(&spec.format(&val, env)?);
+                                }
                             }
                             s.push_str(&part);
                         }
@@ -629,6 +645,11 @@ code:
                     env.touch_array_stack(count);
                     Ok(())
                 }),
+                Instr::CheckAnnotation { annotation, span } => {
+                    let (annotation, span) = (annotation.clone(), *span);
+                    self.check_annotation(&annotation, span);
+                    Ok(())
+                }
                 &Instr::PushTemp { stack, count, span } => self.with_span(span, |env| {
                     for i in 0..count {
                         let value = env.pop(i + 1)?;
@@ -981,6 +1002,34 @@ code:
     pub fn instrs(&self, slice: FuncSlice) -> &[Instr] {
         &self.asm.instrs[slice.start..][..slice.len]
     }
+    /// Take the diagnostics that were emitted while running
+    ///
+    /// These include annotation mismatches that could only be checked at runtime.
+    /// They are separate from the diagnostics emitted while compiling,
+    /// which are available from [`Compiler::take_diagnostics`].
+    /// Anything that shows compiler diagnostics should show these as well.
+    #[allow(clippy::mutable_key_type)]
+    pub fn take_diagnostics(&mut self) -> BTreeSet<Diagnostic> {
+        take(&mut self.rt.diagnostics)
+    }
+    /// Check the values on the stack against an annotation
+    fn check_annotation(&mut self, annotation: &Annotation, span: usize) {
+        let count = annotation.args.len();
+        if self.rt.stack.len() < count {
+            return;
+        }
+        let args: Vec<Option<ArgInfo>> = (self.rt.stack.iter().rev().zip(&annotation.args))
+            .map(|(value, ann)| Some(ArgInfo::from_value(value, ann)))
+            .collect();
+        if let Err(message) = annotation.check(&args) {
+            self.rt.diagnostics.insert(Diagnostic::new(
+                message,
+                self.asm.spans[span].clone(),
+                DiagnosticKind::Warning,
+                self.asm.inputs.clone(),
+            ));
+        }
+    }
     /// Take the entire stack
     pub fn take_stack(&mut self) -> Vec<Value> {
         for stack in &mut self.rt.temp_stacks {
@@ -1293,6 +1342,7 @@ code:
                 execution_limit: self.rt.execution_limit,
                 execution_start: self.rt.execution_start,
                 output_comments: HashMap::new(),
+                diagnostics: BTreeSet::new(),
                 memo: self.rt.memo.clone(),
                 thread,
             },
@@ -0,0 +1,44 @@
+//! Uiua modules that are bundled with the interpreter
+
+use std::path::{Path, PathBuf};
+
+/// The import path prefix reserved for bundled standard library modules
+pub const STD_PREFIX: &str = "std";
+
+/// The bundled standard library modules
+///
+/// Each entry is a module's name and its source code.
+pub const STD_MODULES: &[(&str, &str)] = &[
+    ("matrix", include_str!("std/matrix.ua")),
+    ("strings", include_str!("std/strings.ua")),
+];
+
+/// Resolve an import path to a standard library module
+///
+/// Returns `None` if the path does not start with the standard library prefix.
+/// This should only be used if there is no file at the path.
+pub(crate) fn resolve_std_import(path: &Path) -> Option<Result<PathBuf, String>> {
+    let name = path.strip_prefix(STD_PREFIX).ok()?.with_extension("");
+    let name = name.to_string_lossy();
+    Some(if std_module_source(&name).is_some() {
+        Ok(Path::new(STD_PREFIX).join(&*name))
+    } else {
+        let names: Vec<&str> = STD_MODULES.iter().map(|(name, _)| *name).collect();
+        Err(format!(
+            "Unknown standard library module `{name}`. \
+            Available modules are: {}",
+            names.join(", ")
+        ))
+    })
+}
+
+/// Get the source code of a resolved standard library module path
+pub(crate) fn std_module(path: &Path) -> Option<&'static str> {
+    std_module_source(&path.strip_prefix(STD_PREFIX).ok()?.to_string_lossy())
+}
+
+fn std_module_source(name: &str) -> Option<&'static str> {
+    (STD_MODULES.iter())
+        .find(|(module, _)| *module == name)
+        .map(|(_, src)| *src)
+}
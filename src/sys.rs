@@ -1,8 +1,10 @@
 use std::{
     any::Any,
+    f64::consts::{PI, TAU},
     fmt,
     io::{stderr, stdin, Read, Write},
     path::Path,
+    str::FromStr,
     sync::{Arc, OnceLock},
     time::Duration,
 };
@@ -224,6 +226,18 @@ sys_op! {
     ///
     /// [import] can only be used as the first function in a binding.
     /// ex! &i "example.ua" "Double" 5
+    ///
+    /// If the path does not exist, it may instead start with the name of a dependency listed in a `uiua.toml` package manifest.
+    /// The nearest manifest to the importing file is used.
+    /// A dependency's name alone imports its entry file, and a path after the name is relative to the dependency's root.
+    /// Dependencies may be local paths or git repositories.
+    /// Git dependencies must be installed with `uiua install` before they can be imported. `uiua run` installs them automatically.
+    /// They are checked out into `.uiua/deps` and pinned to a commit in `uiua.lock`.
+    ///
+    /// Paths that start with `std/` import modules from the standard library that is bundled with the interpreter, unless a file exists at that path.
+    /// ex: Str ← &i "std/strings"
+    ///   : Str "Split" ", " "a, b, c"
+    /// The standard library modules are `std/strings` and `std/matrix`.
     (2, Import, Filesystem, "&i", "import"),
     /// Invoke a path with the system's default program
     (1(1), Invoke, Command, "&invk", "invoke"),
@@ -296,9 +310,11 @@ sys_op! {
     (2(0), FWriteAll, Filesystem, "&fwa", "file - write all"),
     /// Decode an image from a byte array
     ///
-    /// Supported formats are `jpg`, `png`, `bmp`, `gif`, `ico`, and `qoi`.
+    /// Supported formats are `jpg`, `png`, `bmp`, `gif`, `ico`, `qoi`, and `webp`.
     ///
-    /// See also: [&ime]
+    /// The result is always an RGBA image. Images with 16 bits per channel keep their full precision.
+    ///
+    /// See also: [&ime] [&imm]
     (1, ImDecode, Images, "&imd", "image - decode"),
     /// Encode an image into a byte array with the specified format
     ///
@@ -314,15 +330,31 @@ sys_op! {
     /// A length 3 last axis is an RGB image.
     /// A length 4 last axis is an RGB image with an alpha channel.
     ///
-    /// Supported formats are `jpg`, `png`, `bmp`, `gif`, `ico`, and `qoi`.
+    /// Supported formats are `jpg`, `png`, `bmp`, `gif`, `ico`, `qoi`, and `webp`.
+    ///
+    /// PNG images can be encoded with 16 bits per channel by using the format `png:16`.
+    /// ex: ⧻ &ime "png:16" ÷255⇡256_256
     ///
     /// See also: [&ims] [&imd]
     (2, ImEncode, Images, "&ime", "image - encode"),
+    /// Get the metadata of an image from a byte array
+    ///
+    /// Returns the width, height, color type, and bit depth of the image.
+    /// Only the header of the image is read, so this is much faster than decoding it with [&imd].
+    ///
+    /// The color type is one of `"gray"`, `"gray alpha"`, `"rgb"`, or `"rgba"`.
+    /// The bit depth is the number of bits per channel.
+    /// ex: &imm &ime "png:16" ÷3⇡3_4
+    ///
+    /// See also: [&imd]
+    (1(4), ImMetadata, Images, "&imm", "image - metadata"),
     /// Show an image
     ///
     /// How the image is shown depends on the system backend.
     ///
     /// In the default backend, the image is shown in the terminal.
+    /// The Kitty graphics protocol or sixel graphics are used if the terminal supports them. Otherwise, the image is drawn with colored half-block characters.
+    /// The protocol can be chosen with the `UIUA_IMAGE_PROTOCOL` environment variable or the `--image-protocol` option, which can be `auto`, `kitty`, `sixel`, or `halfblock`.
     /// On the web, the image is shown in the output area.
     ///
     /// The image must be a rank 2 or 3 numeric array.
@@ -337,34 +369,123 @@ sys_op! {
     ///
     /// See also: [&ime]
     (1(0), ImShow, Images, "&ims", "image - show"),
+    /// Render text into an image
+    ///
+    /// The first argument is the font, the second is the font size in pixels, and the third is the text.
+    /// Returns a rank 2 array of how much each pixel is covered by the text, from `0` to `1`.
+    ///
+    /// The font may be `"uiua"` for the built-in Uiua386 font, the path to a TrueType or OpenType font file, or the bytes of a font.
+    ///
+    /// ex: &imtx "uiua" 32 "Hello, World!"
+    /// Lines are separated by newlines.
+    /// ex: &imtx "uiua" 20 "Uiua\nis\nfun"
+    /// The result can be used as a mask to color the text.
+    /// ex: ⍉[⊃(∘|×0|¬)] &imtx "uiua" 48 "Uiua"
+    ///
+    /// See also: [&ime] [&ims]
+    (3, ImText, Images, "&imtx", "image - text"),
+    /// Plot data into an image
+    ///
+    /// The first argument is a string of space-separated options, and the second is the data.
+    /// Returns an RGB image that can be used with [&ims] or [&ime].
+    ///
+    /// A rank 1 array is a list of y values.
+    /// A rank 2 array is a list of series of y values.
+    /// In both cases, the x values are the indices.
+    /// A rank 3 array is a list of series, each of which is a row of x values and a row of y values.
+    /// Non-finite values are skipped.
+    ///
+    /// The options are:
+    /// - `line` - Connect the points with lines. This is the default.
+    /// - `scatter` - Draw the points without connecting them
+    /// - `bar` - Draw bars from zero to each point. Multiple series are grouped.
+    /// - `WIDTHxHEIGHT` - Set the size of the image. The default is `640x480`.
+    ///
+    /// ex: &plot "line" ○÷10⇡63
+    /// ex: &plot "bar 400x300" [3 1 4 1 5 9 2 6]
+    /// ex: &plot "scatter" ¤⊟⊃∘(ⁿ2) -5⇡11
+    /// ex: &plot "line" [⊃○∘ ÷10⇡63]
+    ///
+    /// See also: [&imtx]
+    (2, Plot, Images, "&plot", "plot"),
     /// Decode a gif from a byte array
     ///
     /// Returns a framerate in seconds and a rank 4 array of RGBA frames.
+    /// The framerate is the average over all frames.
     ///
-    /// See also: [&gife]
+    /// See also: [&gifdd] [&gife]
     (1(2), GifDecode, Gifs, "&gifd", "gif - decode"),
+    /// Decode a gif from a byte array with the delay of each frame
+    ///
+    /// Returns a list of the delays of each frame in seconds and a rank 4 array of RGBA frames.
+    /// The delays can be passed to [&gife] to re-encode the gif with the same timing.
+    ///
+    /// See also: [&gifd]
+    (1(2), GifDecodeDelays, Gifs, "&gifdd", "gif - decode with delays"),
     /// Encode a gif into a byte array
     ///
-    /// The first argument is a framerate in seconds.
+    /// The first argument is either a framerate in seconds or a list of the delays of each frame in seconds.
     /// The second argument is the gif data and must be a rank 3 or 4 numeric array.
     /// The rows of the array are the frames of the gif, and their format must conform to that of [&ime].
     ///
-    /// See also: [&gifs] [&gifd]
+    /// Delays are rounded to hundredths of a second without accumulating error, so `&gife 30` does not drift.
+    ///
+    /// See also: [&gifeo] [&gifs] [&gifd]
     (2, GifEncode, Gifs, "&gife", "gif - encode"),
+    /// Encode a gif into a byte array with options
+    ///
+    /// The first argument is a string of space-separated options.
+    /// The second and third arguments are the same as those of [&gife].
+    ///
+    /// The options are:
+    /// - `loop:n` - Repeat the animation `n` times. `loop:inf`, the default, repeats forever.
+    /// - `transparent` - Make pixels with an alpha below `0.5` transparent
+    /// - `palette:global` - Use one palette for all frames
+    /// - `palette:frame` - Give each frame its own palette. This is the default.
+    /// - `dither` - Dither colors that are not in the palette
+    ///
+    /// Palettes are exact if there are at most 256 colors. Otherwise, colors are quantized.
+    /// ex: ⧻ &gifeo "loop:1 palette:global dither" [0.1 0.2 0.3] ÷3⇡3_8_8
+    ///
+    /// See also: [&gife]
+    (3, GifEncodeOptions, Gifs, "&gifeo", "gif - encode with options"),
     /// Show a gif
     ///
-    /// The first argument is a framerate in seconds.
+    /// The first argument is either a framerate in seconds or a list of the delays of each frame in seconds.
     /// The second argument is the gif data and must be a rank 3 or 4 numeric array.
     /// The rows of the array are the frames of the gif, and their format must conform to that of [&ime].
     ///
+    /// In the default backend, the gif is animated in the terminal the same way [&ims] shows images.
+    ///
     /// See also: [&gife]
     (2(0), GifShow, Gifs, "&gifs", "gif - show"),
+    /// Encode an animated PNG into a byte array
+    ///
+    /// The arguments are the same as those of [&gife].
+    /// Unlike GIFs, animated PNGs are not limited to 256 colors and support partial transparency.
+    /// Delays are rounded to milliseconds.
+    ///
+    /// ex: ⧻ &apnge 10 ÷9⇡10_3_3
+    ///
+    /// See also: [&gife]
+    (2, ApngEncode, Gifs, "&apnge", "apng - encode"),
     /// Decode audio from a byte array
     ///
-    /// Only the `wav` format is supported.
+    /// The samples are a rank 1 array for mono audio or a rank 2 array where each row is a channel.
     ///
-    /// See also: [&ae]
+    /// Supported formats are `wav`, `flac`, `ogg` (Vorbis), and `mp3`.
+    ///
+    /// See also: [&ae] [&adm]
     (1, AudioDecode, Audio, "&ad", "audio - decode"),
+    /// Get the metadata of audio from a byte array
+    ///
+    /// Returns the sample rate and the bit depth of the audio.
+    /// Lossy formats have no bit depth, so it is `0` for them.
+    /// Only the header of the audio is read, so this is much faster than decoding it with [&ad].
+    /// ex: &adm &ae "wav:24" [0.5 0 ¯0.5]
+    ///
+    /// See also: [&ad]
+    (1(2), AudioMetadata, Audio, "&adm", "audio - metadata"),
     /// Encode audio into a byte array
     ///
     /// The first argument is the format, and the second is the audio samples.
@@ -377,7 +498,11 @@ sys_op! {
     /// The samples must be between -1 and 1.
     /// The sample rate is [&asr].
     ///
-    /// Only the `wav` format is supported.
+    /// Supported formats are `wav` and `flac`.
+    /// The sample format can be chosen by following the format with a `:` and one of these options:
+    /// - `wav` - `8`, `16`, `24`, or `32` for integer samples of that many bits, or `f32` for 32-bit float samples
+    /// - `flac` - `8`, `16`, or `24` for samples of that many bits. The default is `16`.
+    /// ex: ⧻ &ae "wav:24" ÷4○×τ×220÷&asr⇡&asr
     ///
     /// See also: [&ap] [&ad]
     (2, AudioEncode, Audio, "&ae", "audio - encode"),
@@ -401,6 +526,15 @@ sys_op! {
     /// Pass that to a periodic function, and you get a nice tone!
     /// ex: ÷4○×τ×220 ÷:⇡×, 4 &asr
     (0, AudioSampleRate, Audio, "&asr", "audio - sample rate"),
+    /// Resample audio to a different sample rate
+    ///
+    /// The first argument is the target sample rate, the second is the source sample rate, and the third is the audio samples.
+    /// The samples must be a rank 1 or 2 numeric array, as with [&ap].
+    ///
+    /// The audio is interpolated with a windowed sinc filter, which avoids aliasing when downsampling.
+    /// ex: ⧻ &ars 22050 &asr ÷4○×τ×220÷&asr⇡&asr
+    /// Audio decoded with [&ad] should be resampled to the [&asr] with `&ars &asr ⊃(⊙◌&adm|&ad)` so that it plays at the right pitch.
+    (3, AudioResample, Audio, "&ars", "audio - resample"),
     /// Synthesize and stream audio
     ///
     /// Expects a function that takes a list of sample times and returns a list of samples.
@@ -417,9 +551,57 @@ sys_op! {
     ///   : Hit ← ×↯:Noise △.×<0.6:>0.5.÷2◿2
     ///   : Hat ← ×0.3×↯:Noise △.<0.1÷0.25◿0.25
     ///   : &ast(÷3/+[⊃(Hat|Kick|Hit|Bass)]×Sp)
+    /// If the function returns an empty array, the stream ends.
     /// On the web, this will simply use the function to generate a fixed amount of audio.
     /// How long the audio is can be configure in the editor settings.
+    /// The native interpreter can render the audio to a file instead of playing it with `uiua run --render-audio out.wav --duration 10`.
     (0(0)[1], AudioStream, Audio, "&ast", "audio - stream"),
+    /// Read a chunk of audio input
+    ///
+    /// Expects the maximum number of samples to read per channel.
+    /// Returns a rank 1 array for mono input or a rank 2 array where each row is a channel.
+    /// The returned array has fewer samples than requested if the input runs out, and it is empty once the input is exhausted.
+    ///
+    /// The native interpreter reads input from a WAV file or raw PCM on stdin, given by `uiua run --audio-input`.
+    ///
+    /// See also: [&ais] [&air]
+    (1, AudioInput, Audio, "&ai", "audio - input"),
+    /// Get the sample rate of the audio input
+    ///
+    /// See also: [&ai] [&ais]
+    (0, AudioInputSampleRate, Audio, "&air", "audio - input sample rate"),
+    /// Process and stream audio input
+    ///
+    /// Expects a function that takes a chunk of input samples and returns a chunk of output samples.
+    /// The chunks have the same format as those returned by [&ai]: a rank 1 array for mono or a rank 2 array where each row is a channel.
+    /// The output may have 1 or 2 channels.
+    /// The function is called repeatedly until the input is exhausted.
+    ///
+    /// For example, `&ais∘` plays the audio input unchanged, and `&ais(×0.5)` plays it at half volume.
+    (0(0)[1], AudioInputStream, Audio, "&ais", "audio - input stream"),
+    /// Decode notes from MIDI bytes
+    ///
+    /// Returns a table of notes, where each row is `[track time pitch velocity duration]`.
+    /// Times and durations are measured in beats (quarter notes).
+    /// Pitches are MIDI note numbers, where `60` is middle C.
+    /// Notes are sorted by track, then by time, then by pitch.
+    ///
+    /// ex: &midd &mide [0_0_60_100_1 0_1_64_100_1 1_0_48_80_2]
+    ///
+    /// See also: [&mide]
+    (1, MidiDecode, Audio, "&midd", "midi - decode"),
+    /// Encode notes as MIDI bytes
+    ///
+    /// Expects a table of notes, where each row is `[track time pitch velocity duration]`.
+    /// Times and durations are measured in beats (quarter notes).
+    /// Pitches and velocities must be integers between 0 and 127 and 1 and 127 respectively.
+    /// Notes shorter than a tick (1/480 of a beat) are lengthened to 1 tick.
+    /// Track `n` is played on MIDI channel `n` modulo `16`, at 120 beats per minute.
+    ///
+    /// ex: ⧻ &mide [0_0_60_100_1 0_1_64_100_1 0_2_67_100_2]
+    ///
+    /// See also: [&midd]
+    (1, MidiEncode, Audio, "&mide", "midi - encode"),
     /// Create a TCP listener and bind it to an address
     (1, TcpListen, Tcp, "&tcpl", "tcp - listen"),
     /// Accept a connection with a TCP listener
@@ -651,9 +833,22 @@ pub trait SysBackend: Any + Send + Sync + 'static {
         44100
     }
     /// Stream audio
+    ///
+    /// Streaming should stop if the function returns no samples.
     fn stream_audio(&self, f: AudioStreamFn) -> Result<(), String> {
         Err("Streaming audio not supported in this environment".into())
     }
+    /// Read at most `count` samples per channel of audio input
+    ///
+    /// Should return a rank 1 array for mono input or a rank 2 array where each row is a channel.
+    /// Should return an empty array once the input is exhausted.
+    fn audio_input(&self, count: usize) -> Result<Array<f64>, String> {
+        Err("Audio input is not supported in this environment".into())
+    }
+    /// Get the sample rate of the audio input
+    fn audio_input_sample_rate(&self) -> u32 {
+        self.audio_sample_rate()
+    }
     /// Create a TCP listener and bind it to an address
     fn tcp_listen(&self, addr: &str) -> Result<Handle, String> {
         Err("TCP listeners are not supported in this environment".into())
@@ -1120,19 +1315,8 @@ impl SysOp {
                         _ => return Err(env.error("Image bytes must be a numeric array")),
                     };
                     let image = image::load_from_memory(&bytes)
-                        .map_err(|e| env.error(format!("Failed to read image: {}", e)))?
-                        .into_rgba8();
-                    let shape =
-                        crate::Shape::from([image.height() as usize, image.width() as usize, 4]);
-                    let array = Array::<f64>::new(
-                        shape,
-                        image
-                            .into_raw()
-                            .into_iter()
-                            .map(|b| b as f64 / 255.0)
-                            .collect::<crate::cowslice::CowSlice<_>>(),
-                    );
-                    env.push(array);
+                        .map_err(|e| env.error(format!("Failed to read image: {}", e)))?;
+                    env.push(image_to_array(image));
                 }
                 #[cfg(not(feature = "image"))]
                 return Err(env.error("Image decoding is not supported in this environment"));
@@ -1146,11 +1330,23 @@ impl SysOp {
                     let value = env.pop(2)?;
                     let output_format = match format.as_str() {
                         "jpg" | "jpeg" => ImageOutputFormat::Jpeg(100),
-                        "png" => ImageOutputFormat::Png,
+                        "png" | "png:8" => ImageOutputFormat::Png,
+                        "png:16" => {
+                            let bytes = value_to_image16(&value)
+                                .and_then(|image| image_to_bytes(&image, ImageOutputFormat::Png))
+                                .map_err(|e| env.error(e))?;
+                            env.push(Array::<u8>::from(bytes.as_slice()));
+                            return Ok(());
+                        }
                         "bmp" => ImageOutputFormat::Bmp,
                         "gif" => ImageOutputFormat::Gif,
                         "ico" => ImageOutputFormat::Ico,
                         "qoi" => ImageOutputFormat::Qoi,
+                        "webp" => {
+                            let bytes = value_to_webp_bytes(&value).map_err(|e| env.error(e))?;
+                            env.push(Array::<u8>::from(bytes.as_slice()));
+                            return Ok(());
+                        }
                         format => {
                             return Err(env.error(format!("Invalid image format: {}", format)))
                         }
@@ -1162,6 +1358,27 @@ impl SysOp {
                 #[cfg(not(feature = "image"))]
                 return Err(env.error("Image encoding is not supported in this environment"));
             }
+            SysOp::ImMetadata => {
+                #[cfg(feature = "image")]
+                {
+                    let bytes = match env.pop(1)? {
+                        #[cfg(feature = "bytes")]
+                        Value::Byte(arr) if arr.rank() == 1 => arr.data.iter().copied().collect(),
+                        Value::Num(arr) if arr.rank() == 1 => {
+                            arr.data.iter().map(|&x| x as u8).collect()
+                        }
+                        _ => return Err(env.error("Image bytes must be a rank 1 numeric array")),
+                    };
+                    let (width, height, color_type, bit_depth) =
+                        image_metadata(bytes).map_err(|e| env.error(e))?;
+                    env.push(bit_depth as f64);
+                    env.push(color_type);
+                    env.push(height as f64);
+                    env.push(width as f64);
+                }
+                #[cfg(not(feature = "image"))]
+                return Err(env.error("Image decoding is not supported in this environment"));
+            }
             SysOp::ImShow => {
                 #[cfg(feature = "image")]
                 {
@@ -1172,26 +1389,106 @@ impl SysOp {
                 #[cfg(not(feature = "image"))]
                 return Err(env.error("Image encoding is not supported in this environment"));
             }
+            SysOp::ImText => {
+                #[cfg(feature = "font")]
+                {
+                    let font = env.pop(1)?;
+                    let size = env.pop(2)?.as_num(env, "Font size must be a number")?;
+                    let text = env.pop(3)?.as_string(env, "Text must be a string")?;
+                    if !(size.is_finite() && size > 0.0) {
+                        return Err(env.error(format!(
+                            "Font size must be a positive number, but it is {size}"
+                        )));
+                    }
+                    let loaded;
+                    let font = match font {
+                        Value::Char(_) => {
+                            let name = font.as_string(env, "Font name must be a string")?;
+                            match builtin_font(&name) {
+                                Some(font) => font,
+                                None => {
+                                    let bytes = (env.rt.backend)
+                                        .file_read_all(Path::new(&name))
+                                        .map_err(|e| env.error(e))?;
+                                    loaded = load_font(bytes).map_err(|e| env.error(e))?;
+                                    &loaded
+                                }
+                            }
+                        }
+                        font => {
+                            let bytes =
+                                font.as_bytes(env, "Font must be a name, path, or bytes")?;
+                            loaded = load_font(bytes).map_err(|e| env.error(e))?;
+                            &loaded
+                        }
+                    };
+                    env.push(rasterize_text(font, size as f32, &text));
+                }
+                #[cfg(not(feature = "font"))]
+                return Err(env.error("Text rendering is not supported in this environment"));
+            }
+            SysOp::Plot => {
+                #[cfg(feature = "plot")]
+                {
+                    let options = env
+                        .pop(1)?
+                        .as_string(env, "Plot options must be a string")?;
+                    let options: crate::PlotOptions = options.parse().map_err(|e| env.error(e))?;
+                    let data = env.pop(2)?;
+                    let series = crate::PlotSeries::from_value(&data).map_err(|e| env.error(e))?;
+                    env.push(crate::plot(&series, options));
+                }
+                #[cfg(not(feature = "plot"))]
+                return Err(env.error("Plotting is not supported in this environment"));
+            }
             SysOp::GifDecode => {
                 #[cfg(feature = "gif")]
                 {
                     let bytes = env
                         .pop(1)?
                         .as_bytes(env, "Gif bytes must be a byte array")?;
-                    let (frame_rate, value) =
-                        gif_bytes_to_value(&bytes).map_err(|e| env.error(e))?;
+                    let (value, delays) = gif_bytes_to_value(&bytes).map_err(|e| env.error(e))?;
+                    let frame_rate = delays.len() as f64 / delays.iter().sum::<f64>();
                     env.push(value);
                     env.push(frame_rate);
                 }
                 #[cfg(not(feature = "gif"))]
                 return Err(env.error("GIF encoding is not supported in this environment"));
             }
+            SysOp::GifDecodeDelays => {
+                #[cfg(feature = "gif")]
+                {
+                    let bytes = env
+                        .pop(1)?
+                        .as_bytes(env, "Gif bytes must be a byte array")?;
+                    let (value, delays) = gif_bytes_to_value(&bytes).map_err(|e| env.error(e))?;
+                    env.push(value);
+                    env.push(Array::from(delays.as_slice()));
+                }
+                #[cfg(not(feature = "gif"))]
+                return Err(env.error("GIF decoding is not supported in this environment"));
+            }
             SysOp::GifEncode => {
                 #[cfg(feature = "gif")]
                 {
-                    let delay = env.pop(1)?.as_num(env, "Delay must be a number")?;
+                    let delays = frame_delays(env.pop(1)?, env)?;
                     let value = env.pop(2)?;
-                    let bytes = value_to_gif_bytes(&value, delay).map_err(|e| env.error(e))?;
+                    let bytes = value_to_gif_bytes_with(&value, &delays, GifOptions::default())
+                        .map_err(|e| env.error(e))?;
+                    env.push(Array::<u8>::from(bytes.as_slice()));
+                }
+                #[cfg(not(feature = "gif"))]
+                return Err(env.error("GIF encoding is not supported in this environment"));
+            }
+            SysOp::GifEncodeOptions => {
+                #[cfg(feature = "gif")]
+                {
+                    let options = env.pop(1)?.as_string(env, "GIF options must be a string")?;
+                    let options: GifOptions = options.parse().map_err(|e| env.error(e))?;
+                    let delays = frame_delays(env.pop(2)?, env)?;
+                    let value = env.pop(3)?;
+                    let bytes = value_to_gif_bytes_with(&value, &delays, options)
+                        .map_err(|e| env.error(e))?;
                     env.push(Array::<u8>::from(bytes.as_slice()));
                 }
                 #[cfg(not(feature = "gif"))]
@@ -1200,14 +1497,26 @@ impl SysOp {
             SysOp::GifShow => {
                 #[cfg(feature = "gif")]
                 {
-                    let delay = env.pop(1)?.as_num(env, "Delay must be a number")?;
+                    let delays = frame_delays(env.pop(1)?, env)?;
                     let value = env.pop(2)?;
-                    let bytes = value_to_gif_bytes(&value, delay).map_err(|e| env.error(e))?;
+                    let bytes = value_to_gif_bytes_with(&value, &delays, GifOptions::default())
+                        .map_err(|e| env.error(e))?;
                     env.rt.backend.show_gif(bytes).map_err(|e| env.error(e))?;
                 }
                 #[cfg(not(feature = "gif"))]
                 return Err(env.error("GIF encoding is not supported in this environment"));
             }
+            SysOp::ApngEncode => {
+                #[cfg(feature = "apng")]
+                {
+                    let delays = frame_delays(env.pop(1)?, env)?;
+                    let value = env.pop(2)?;
+                    let bytes = value_to_apng_bytes(&value, &delays).map_err(|e| env.error(e))?;
+                    env.push(Array::<u8>::from(bytes.as_slice()));
+                }
+                #[cfg(not(feature = "apng"))]
+                return Err(env.error("APNG encoding is not supported in this environment"));
+            }
             SysOp::AudioDecode => {
                 #[cfg(feature = "audio_encode")]
                 {
@@ -1233,12 +1542,31 @@ impl SysOp {
                         }
                         _ => return Err(env.error("Audio bytes be a numeric array")),
                     };
-                    let array = array_from_wav_bytes(&bytes, env).map_err(|e| env.error(e))?;
+                    let array = array_from_audio_bytes(&bytes, env)?;
                     env.push(array);
                 }
                 #[cfg(not(feature = "audio_encode"))]
                 return Err(env.error("Audio decoding is not supported in this environment"));
             }
+            SysOp::AudioMetadata => {
+                #[cfg(feature = "audio_encode")]
+                {
+                    let bytes: Vec<u8> = match env.pop(1)? {
+                        #[cfg(feature = "bytes")]
+                        Value::Byte(arr) if arr.rank() == 1 => arr.data.iter().copied().collect(),
+                        Value::Num(arr) if arr.rank() == 1 => {
+                            arr.data.iter().map(|&x| x as u8).collect()
+                        }
+                        _ => return Err(env.error("Audio bytes must be a rank 1 numeric array")),
+                    };
+                    let (sample_rate, bit_depth) =
+                        audio_metadata(&bytes).map_err(|e| env.error(e))?;
+                    env.push(bit_depth);
+                    env.push(sample_rate);
+                }
+                #[cfg(not(feature = "audio_encode"))]
+                return Err(env.error("Audio decoding is not supported in this environment"));
+            }
             SysOp::AudioEncode => {
                 #[cfg(feature = "audio_encode")]
                 {
@@ -1246,13 +1574,33 @@ impl SysOp {
                         .pop(1)?
                         .as_string(env, "Audio format must be a string")?;
                     let value = env.pop(2)?;
-                    let bytes = match format.as_str() {
-                        "wav" => value_to_wav_bytes(&value, env.rt.backend.audio_sample_rate())
-                            .map_err(|e| env.error(e))?,
-                        format => {
+                    let sample_rate = env.rt.backend.audio_sample_rate();
+                    let (format, sample_format) = match format.split_once(':') {
+                        Some((format, sample_format)) => (format, Some(sample_format)),
+                        None => (format.as_str(), None),
+                    };
+                    let bytes = match (format, sample_format) {
+                        ("wav", None) => value_to_wav_bytes(&value, sample_rate),
+                        ("wav", Some(bits @ ("8" | "16" | "24" | "32"))) => {
+                            let bits = bits.parse().unwrap();
+                            value_to_wav_bytes_as(&value, sample_rate, SampleFormat::Int, bits)
+                        }
+                        ("wav", Some("f32")) => {
+                            value_to_wav_bytes_as(&value, sample_rate, SampleFormat::Float, 32)
+                        }
+                        ("flac", None) => value_to_flac_bytes(&value, sample_rate, 16),
+                        ("flac", Some(bits @ ("8" | "16" | "24"))) => {
+                            value_to_flac_bytes(&value, sample_rate, bits.parse().unwrap())
+                        }
+                        ("wav" | "flac", Some(sample_format)) => {
+                            return Err(env
+                                .error(format!("Invalid {format} sample format: {sample_format}")))
+                        }
+                        (format, _) => {
                             return Err(env.error(format!("Invalid audio format: {}", format)))
                         }
-                    };
+                    }
+                    .map_err(|e| env.error(e))?;
                     env.push(Array::<u8>::from(bytes.as_slice()));
                 }
                 #[cfg(not(feature = "audio_encode"))]
@@ -1273,6 +1621,25 @@ impl SysOp {
                 let sample_rate = env.rt.backend.audio_sample_rate();
                 env.push(f64::from(sample_rate));
             }
+            SysOp::AudioResample => {
+                let target = env
+                    .pop(1)?
+                    .as_num(env, "Target sample rate must be a number")?;
+                let source = env
+                    .pop(2)?
+                    .as_num(env, "Source sample rate must be a number")?;
+                let value = env.pop(3)?;
+                let channels = value_to_audio_channels(&value).map_err(|e| env.error(e))?;
+                let channels =
+                    resample_audio_channels(channels, source, target).map_err(|e| env.error(e))?;
+                let array = if value.rank() == 1 {
+                    channels.into_iter().next().unwrap().into_iter().collect()
+                } else {
+                    let rows = channels.into_iter().map(|ch| ch.into_iter().collect());
+                    Array::from_row_arrays(rows, env)?
+                };
+                env.push(array);
+            }
             SysOp::AudioStream => {
                 let f = env.pop_function()?;
                 if f.signature() != (1, 1) {
@@ -1287,26 +1654,74 @@ impl SysOp {
                     let time_array = Array::<f64>::from(time_array);
                     stream_env.push(time_array);
                     stream_env.call(f.clone())?;
-                    let samples = &stream_env.pop(1)?;
-                    let samples = samples.as_num_array().ok_or_else(|| {
-                        stream_env.error("Audio stream function must return a numeric array")
-                    })?;
-                    match samples.shape().dims() {
-                        [_] => Ok(samples.data.iter().map(|&x| [x, x]).collect()),
-                        [_, 2] => Ok(samples
-                            .data
-                            .chunks(2)
-                            .map(|s| [s[0], s.get(1).copied().unwrap_or(0.0)])
-                            .collect()),
-                        _ => Err(stream_env.error(format!(
-                            "Audio stream function must return a rank 1 or 2 array, but returned a rank {} array",
-                            samples.rank()
-                        ))),
+                    let samples = stream_env.pop(1)?;
+                    stream_output_to_stereo(&samples, &stream_env)
+                })) {
+                    return Err(env.error(e));
+                }
+            }
+            SysOp::AudioInput => {
+                let count = env
+                    .pop(1)?
+                    .as_nat(env, "Sample count must be a natural number")?;
+                let samples = env
+                    .rt
+                    .backend
+                    .audio_input(count)
+                    .map_err(|e| env.error(e))?;
+                env.push(samples);
+            }
+            SysOp::AudioInputSampleRate => {
+                let sample_rate = env.rt.backend.audio_input_sample_rate();
+                env.push(f64::from(sample_rate));
+            }
+            SysOp::AudioInputStream => {
+                let f = env.pop_function()?;
+                if f.signature() != (1, 1) {
+                    return Err(env.error(format!(
+                        "&ais's function's signature must be {}, but it is {}",
+                        Signature::new(1, 1),
+                        f.signature()
+                    )));
+                }
+                let mut stream_env = env.clone();
+                if let Err(e) = env.rt.backend.stream_audio(Box::new(move |time_array| {
+                    let input = (stream_env.rt.backend)
+                        .audio_input(time_array.len())
+                        .map_err(|e| stream_env.error(e))?;
+                    if input.element_count() == 0 {
+                        return Ok(Vec::new());
                     }
+                    stream_env.push(input);
+                    stream_env.call(f.clone())?;
+                    let samples = stream_env.pop(1)?;
+                    stream_channels_to_stereo(&samples, &stream_env)
                 })) {
                     return Err(env.error(e));
                 }
             }
+            SysOp::MidiDecode => {
+                #[cfg(feature = "midi")]
+                {
+                    let bytes = env
+                        .pop(1)?
+                        .as_bytes(env, "MIDI bytes must be a byte array")?;
+                    let notes = midi_bytes_to_notes(&bytes).map_err(|e| env.error(e))?;
+                    env.push(notes);
+                }
+                #[cfg(not(feature = "midi"))]
+                return Err(env.error("MIDI decoding is not supported in this environment"));
+            }
+            SysOp::MidiEncode => {
+                #[cfg(feature = "midi")]
+                {
+                    let notes = env.pop(1)?;
+                    let bytes = notes_to_midi_bytes(&notes).map_err(|e| env.error(e))?;
+                    env.push(Array::<u8>::from(bytes.as_slice()));
+                }
+                #[cfg(not(feature = "midi"))]
+                return Err(env.error("MIDI encoding is not supported in this environment"));
+            }
             SysOp::Sleep => {
                 let seconds = env
                     .pop(1)?
@@ -1553,6 +1968,103 @@ pub fn value_to_image_bytes(value: &Value, format: ImageOutputFormat) -> Result<
     image_to_bytes(&value_to_image(value)?, format)
 }
 
+/// The name of the font built into the interpreter
+#[cfg(feature = "font")]
+pub const BUILTIN_FONT: &str = "uiua";
+
+/// Get the built-in font by name
+///
+/// Other fonts must be loaded from a file.
+#[cfg(feature = "font")]
+pub fn builtin_font(name: &str) -> Option<&'static fontdue::Font> {
+    static FONT: OnceLock<fontdue::Font> = OnceLock::new();
+    (name == BUILTIN_FONT).then(|| {
+        FONT.get_or_init(|| load_font(include_bytes!("fonts/Uiua386.ttf").as_slice()).unwrap())
+    })
+}
+
+#[cfg(feature = "font")]
+fn load_font<B: std::ops::Deref<Target = [u8]>>(bytes: B) -> Result<fontdue::Font, String> {
+    fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+        .map_err(|e| format!("Failed to load font: {e}"))
+}
+
+/// Render text into a rank 2 array of pixel coverage
+#[cfg(feature = "font")]
+pub fn rasterize_text(font: &fontdue::Font, size: f32, text: &str) -> Array<f64> {
+    let line_metrics = (font.horizontal_line_metrics(size)).unwrap_or(fontdue::LineMetrics {
+        ascent: size,
+        descent: 0.0,
+        line_gap: 0.0,
+        new_line_size: size,
+    });
+    // Lay out the glyphs
+    let mut glyphs = Vec::new();
+    let mut width = 0;
+    for (i, line) in text.lines().enumerate() {
+        let baseline = i as f32 * line_metrics.new_line_size + line_metrics.ascent;
+        let mut x = 0.0;
+        let mut prev = None;
+        for c in line.chars() {
+            if let Some(prev) = prev {
+                x += font.horizontal_kern(prev, c, size).unwrap_or(0.0);
+            }
+            let (metrics, bitmap) = font.rasterize(c, size);
+            let left = x.round() as i32 + metrics.xmin;
+            let top = baseline.round() as i32 - metrics.ymin - metrics.height as i32;
+            width = width.max(left + metrics.width as i32);
+            glyphs.push((left, top, metrics.width, bitmap));
+            x += metrics.advance_width;
+            prev = Some(c);
+        }
+        width = width.max(x.ceil() as i32);
+    }
+    let line_count = text.lines().count().max(1);
+    let height = ((line_count - 1) as f32 * line_metrics.new_line_size + line_metrics.ascent
+        - line_metrics.descent)
+        .ceil()
+        .max(0.0) as usize;
+    let width = width.max(0) as usize;
+    // Draw the glyphs
+    let mut data = vec![0.0; width * height];
+    for (left, top, glyph_width, bitmap) in glyphs {
+        if glyph_width == 0 {
+            continue;
+        }
+        for (j, row) in bitmap.chunks_exact(glyph_width).enumerate() {
+            let y = top + j as i32;
+            if y < 0 || y >= height as i32 {
+                continue;
+            }
+            for (k, &coverage) in row.iter().enumerate() {
+                let x = left + k as i32;
+                if x < 0 || x >= width as i32 {
+                    continue;
+                }
+                let pixel = &mut data[y as usize * width + x as usize];
+                *pixel = (*pixel + coverage as f64 / 255.0).min(1.0);
+            }
+        }
+    }
+    Array::new(crate::Shape::from_iter([height, width]), data.as_slice())
+}
+
+#[doc(hidden)]
+#[cfg(feature = "image")]
+pub fn value_to_webp_bytes(value: &Value) -> Result<Vec<u8>, String> {
+    let image = value_to_image(value)?.into_rgba8();
+    let mut bytes = Vec::new();
+    image::codecs::webp::WebPEncoder::new_lossless(&mut bytes)
+        .encode(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ColorType::Rgba8,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
 #[doc(hidden)]
 #[cfg(feature = "image")]
 pub fn image_to_bytes(image: &DynamicImage, format: ImageOutputFormat) -> Result<Vec<u8>, String> {
@@ -1563,9 +2075,57 @@ pub fn image_to_bytes(image: &DynamicImage, format: ImageOutputFormat) -> Result
     Ok(bytes.into_inner())
 }
 
+/// Convert a decoded image to an RGBA array, keeping the precision of 16-bit channels
 #[doc(hidden)]
 #[cfg(feature = "image")]
-pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
+pub fn image_to_array(image: DynamicImage) -> Array<f64> {
+    let shape = crate::Shape::from([image.height() as usize, image.width() as usize, 4]);
+    let color = image.color();
+    let data: crate::cowslice::CowSlice<f64> = if color.bytes_per_pixel() > color.channel_count() {
+        (image.into_rgba16().into_raw().into_iter())
+            .map(|c| c as f64 / u16::MAX as f64)
+            .collect()
+    } else {
+        (image.into_rgba8().into_raw().into_iter())
+            .map(|c| c as f64 / 255.0)
+            .collect()
+    };
+    Array::new(shape, data)
+}
+
+/// Read the width, height, color type, and bit depth of an image from its header
+#[doc(hidden)]
+#[cfg(feature = "image")]
+pub fn image_metadata(bytes: Vec<u8>) -> Result<(u32, u32, &'static str, u8), String> {
+    use image::{codecs::*, ColorType, ImageDecoder, ImageFormat};
+    fn info<'a>(decoder: impl ImageDecoder<'a>) -> ((u32, u32), ColorType) {
+        (decoder.dimensions(), decoder.color_type())
+    }
+    let format = image::guess_format(&bytes).map_err(|e| format!("Failed to read image: {e}"))?;
+    let cursor = std::io::Cursor::new(bytes);
+    let ((width, height), color_type) = match format {
+        ImageFormat::Png => png::PngDecoder::new(cursor).map(info),
+        ImageFormat::Jpeg => jpeg::JpegDecoder::new(cursor).map(info),
+        ImageFormat::Bmp => bmp::BmpDecoder::new(cursor).map(info),
+        ImageFormat::Gif => gif::GifDecoder::new(cursor).map(info),
+        ImageFormat::Ico => ico::IcoDecoder::new(cursor).map(info),
+        ImageFormat::Qoi => qoi::QoiDecoder::new(cursor).map(info),
+        ImageFormat::WebP => webp::WebPDecoder::new(cursor).map(info),
+        format => return Err(format!("Unsupported image format: {format:?}")),
+    }
+    .map_err(|e| format!("Failed to read image: {e}"))?;
+    let color_name = match color_type.channel_count() {
+        1 => "gray",
+        2 => "gray alpha",
+        3 => "rgb",
+        _ => "rgba",
+    };
+    let bit_depth = color_type.bits_per_pixel() / color_type.channel_count() as u16;
+    Ok((width, height, color_name, bit_depth as u8))
+}
+
+#[cfg(feature = "image")]
+fn image_dims(value: &Value) -> Result<[usize; 3], String> {
     if ![2, 3].contains(&value.rank()) {
         return Err(format!(
             "Image must be a rank 2 or 3 numeric array, but it is a rank-{} {} array",
@@ -1573,6 +2133,54 @@ pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
             value.type_name()
         ));
     }
+    #[allow(clippy::match_ref_pats)]
+    let [height, width, px_size] = match value.shape().dims() {
+        &[a, b] => [a, b, 1],
+        &[a, b, c] => [a, b, c],
+        _ => unreachable!("Shape checked above"),
+    };
+    if !(1..=4).contains(&px_size) {
+        return Err(format!(
+            "For a color image, the last dimension of the image array must be between 1 and 4 but it is {px_size}"
+        ));
+    }
+    Ok([height, width, px_size])
+}
+
+/// Convert a value to an image with 16 bits per channel
+#[doc(hidden)]
+#[cfg(feature = "image")]
+pub fn value_to_image16(value: &Value) -> Result<DynamicImage, String> {
+    let [height, width, px_size] = image_dims(value)?;
+    let channels: Vec<u16> = match value {
+        // Round so that decoded 16-bit images round-trip exactly
+        Value::Num(nums) => (nums.data.iter())
+            .map(|f| (*f * u16::MAX as f64).round() as u16)
+            .collect(),
+        #[cfg(feature = "bytes")]
+        Value::Byte(bytes) => (bytes.data.iter())
+            .map(|&b| (b > 0) as u16 * u16::MAX)
+            .collect(),
+        _ => return Err("Image must be a numeric array".into()),
+    };
+    let (width, height) = (width as u32, height as u32);
+    Ok(match px_size {
+        1 => image::ImageBuffer::<image::Luma<u16>, _>::from_raw(width, height, channels)
+            .map(DynamicImage::from),
+        2 => image::ImageBuffer::<image::LumaA<u16>, _>::from_raw(width, height, channels)
+            .map(DynamicImage::from),
+        3 => image::ImageBuffer::<image::Rgb<u16>, _>::from_raw(width, height, channels)
+            .map(DynamicImage::from),
+        _ => image::ImageBuffer::<image::Rgba<u16>, _>::from_raw(width, height, channels)
+            .map(DynamicImage::from),
+    }
+    .ok_or("Failed to create image")?)
+}
+
+#[doc(hidden)]
+#[cfg(feature = "image")]
+pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
+    let [height, width, px_size] = image_dims(value)?;
     let bytes = match value {
         Value::Num(nums) => nums
             .data
@@ -1583,12 +2191,6 @@ pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
         Value::Byte(bytes) => bytes.data.iter().map(|&b| (b > 0) as u8 * 255).collect(),
         _ => return Err("Image must be a numeric array".into()),
     };
-    #[allow(clippy::match_ref_pats)]
-    let [height, width, px_size] = match value.shape().dims() {
-        &[a, b] => [a, b, 1],
-        &[a, b, c] => [a, b, c],
-        _ => unreachable!("Shape checked above"),
-    };
     Ok(match px_size {
         1 => image::GrayImage::from_raw(width as u32, height as u32, bytes)
             .ok_or("Failed to create image")?
@@ -1599,17 +2201,44 @@ pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
         3 => image::RgbImage::from_raw(width as u32, height as u32, bytes)
             .ok_or("Failed to create image")?
             .into(),
-        4 => image::RgbaImage::from_raw(width as u32, height as u32, bytes)
+        _ => image::RgbaImage::from_raw(width as u32, height as u32, bytes)
             .ok_or("Failed to create image")?
             .into(),
-        n => {
-            return Err(format!(
-                "For a color image, the last dimension of the image array must be between 1 and 4 but it is {n}"
-            ))
-        }
     })
 }
 
+/// Convert the output of an audio stream function to stereo samples
+fn stream_output_to_stereo(samples: &Value, env: &Uiua) -> UiuaResult<Vec<[f64; 2]>> {
+    let samples = samples
+        .as_num_array()
+        .ok_or_else(|| env.error("Audio stream function must return a numeric array"))?;
+    match samples.shape().dims() {
+        [_] => Ok(samples.data.iter().map(|&x| [x, x]).collect()),
+        [_, 2] => Ok(samples
+            .data
+            .chunks(2)
+            .map(|s| [s[0], s.get(1).copied().unwrap_or(0.0)])
+            .collect()),
+        _ => Err(env.error(format!(
+            "Audio stream function must return a rank 1 or 2 array, but returned a rank {} array",
+            samples.rank()
+        ))),
+    }
+}
+
+/// Convert audio where each row is a channel into stereo samples
+fn stream_channels_to_stereo(samples: &Value, env: &Uiua) -> UiuaResult<Vec<[f64; 2]>> {
+    let channels = value_to_audio_channels(samples).map_err(|e| env.error(e))?;
+    match channels.as_slice() {
+        [mono] => Ok(mono.iter().map(|&x| [x, x]).collect()),
+        [left, right] => Ok(left.iter().zip(right).map(|(&l, &r)| [l, r]).collect()),
+        _ => Err(env.error(format!(
+            "Audio input stream function must return 1 or 2 channels, but returned {}",
+            channels.len()
+        ))),
+    }
+}
+
 #[doc(hidden)]
 pub fn value_to_sample(audio: &Value) -> Result<Vec<[f32; 2]>, String> {
     let unrolled: Vec<f32> = match audio {
@@ -1674,9 +2303,10 @@ pub fn value_to_audio_channels(audio: &Value) -> Result<Vec<Vec<f64>>, String> {
             ))
         }
     };
-    if channels.len() > 5 {
+    if channels.len() > u16::MAX as usize {
         return Err(format!(
-            "Audio can have at most 5 channels, but its shape is {}",
+            "Audio can have at most {} channels, but its shape is {}",
+            u16::MAX,
             audio.shape()
         ));
     }
@@ -1687,6 +2317,60 @@ pub fn value_to_audio_channels(audio: &Value) -> Result<Vec<Vec<f64>>, String> {
     Ok(channels)
 }
 
+/// Resample audio channels from one sample rate to another
+///
+/// This uses a Blackman-windowed sinc filter. When downsampling, the filter's
+/// cutoff is lowered to the new Nyquist frequency to prevent aliasing.
+#[doc(hidden)]
+pub fn resample_audio_channels(
+    channels: Vec<Vec<f64>>,
+    source_rate: f64,
+    target_rate: f64,
+) -> Result<Vec<Vec<f64>>, String> {
+    for (name, rate) in [("Source", source_rate), ("Target", target_rate)] {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(format!(
+                "{name} sample rate must be a positive number, but it is {rate}"
+            ));
+        }
+    }
+    if source_rate == target_rate {
+        return Ok(channels);
+    }
+    // The number of zero crossings of the sinc on either side of the center
+    const ZERO_CROSSINGS: f64 = 32.0;
+    let ratio = target_rate / source_rate;
+    let cutoff = ratio.min(1.0);
+    let radius = ZERO_CROSSINGS / cutoff;
+    let sinc = |x: f64| {
+        if x == 0.0 {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        }
+    };
+    let window = |x: f64| 0.42 + 0.5 * (PI * x).cos() + 0.08 * (TAU * x).cos();
+    Ok(channels
+        .into_iter()
+        .map(|channel| {
+            let len = (channel.len() as f64 * ratio).round() as usize;
+            let mut resampled = Vec::with_capacity(len);
+            for j in 0..len {
+                let center = j as f64 / ratio;
+                let start = (center - radius).ceil().max(0.0) as usize;
+                let end = ((center + radius).floor() as usize).min(channel.len().saturating_sub(1));
+                let mut sample = 0.0;
+                for (i, &x) in channel.iter().enumerate().take(end + 1).skip(start) {
+                    let offset = center - i as f64;
+                    sample += x * cutoff * sinc(cutoff * offset) * window(offset / radius);
+                }
+                resampled.push(sample);
+            }
+            resampled
+        })
+        .collect())
+}
+
 #[doc(hidden)]
 #[cfg(feature = "audio_encode")]
 pub fn value_to_wav_bytes(audio: &Value, sample_rate: u32) -> Result<Vec<u8>, String> {
@@ -1706,6 +2390,54 @@ pub fn value_to_wav_bytes(audio: &Value, sample_rate: u32) -> Result<Vec<u8>, St
     }
 }
 
+#[doc(hidden)]
+#[cfg(feature = "audio_encode")]
+pub fn value_to_wav_bytes_as(
+    audio: &Value,
+    sample_rate: u32,
+    sample_format: SampleFormat,
+    bits_per_sample: u16,
+) -> Result<Vec<u8>, String> {
+    const I24_MAX: f64 = ((1 << 23) - 1) as f64;
+    match (sample_format, bits_per_sample) {
+        (SampleFormat::Int, 8) => value_to_wav_bytes_impl(
+            audio,
+            |f| (f * i8::MAX as f64) as i8,
+            8,
+            sample_format,
+            sample_rate,
+        ),
+        (SampleFormat::Int, 16) => value_to_wav_bytes_impl(
+            audio,
+            |f| (f * i16::MAX as f64) as i16,
+            16,
+            sample_format,
+            sample_rate,
+        ),
+        (SampleFormat::Int, 24) => value_to_wav_bytes_impl(
+            audio,
+            |f| (f * I24_MAX).clamp(-I24_MAX, I24_MAX) as i32,
+            24,
+            sample_format,
+            sample_rate,
+        ),
+        (SampleFormat::Int, 32) => value_to_wav_bytes_impl(
+            audio,
+            |f| (f * i32::MAX as f64) as i32,
+            32,
+            sample_format,
+            sample_rate,
+        ),
+        (SampleFormat::Float, 32) => {
+            value_to_wav_bytes_impl(audio, |f| f as f32, 32, sample_format, sample_rate)
+        }
+        (sample_format, bits_per_sample) => Err(format!(
+            "Unsupported sample format: {:?} {} bits per sample",
+            sample_format, bits_per_sample
+        )),
+    }
+}
+
 #[cfg(feature = "audio_encode")]
 fn value_to_wav_bytes_impl<T: hound::Sample + Copy>(
     audio: &Value,
@@ -1771,15 +2503,73 @@ pub fn stereo_to_wave_bytes<T: hound::Sample + Copy>(
     Ok(bytes.into_inner())
 }
 
+#[doc(hidden)]
+#[cfg(feature = "audio_encode")]
+pub fn value_to_flac_bytes(
+    audio: &Value,
+    sample_rate: u32,
+    bits_per_sample: usize,
+) -> Result<Vec<u8>, String> {
+    use flacenc::{component::BitRepr, error::Verify};
+    let channels = value_to_audio_channels(audio)?;
+    if channels.len() > 8 {
+        return Err(format!(
+            "FLAC audio can have at most 8 channels, but there are {}",
+            channels.len()
+        ));
+    }
+    let scale = (1i64 << (bits_per_sample - 1)) as f64;
+    let mut samples = Vec::with_capacity(channels.len() * channels[0].len());
+    for i in 0..channels[0].len() {
+        for channel in &channels {
+            samples.push((channel[i] * scale).clamp(-scale, scale - 1.0) as i32);
+        }
+    }
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| format!("Invalid FLAC encoder configuration: {e}"))?;
+    let source = flacenc::source::MemSource::from_samples(
+        &samples,
+        channels.len(),
+        bits_per_sample,
+        sample_rate as usize,
+    );
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| format!("Failed to encode audio: {e:?}"))?;
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| format!("Failed to write audio: {e}"))?;
+    Ok(sink.as_slice().to_vec())
+}
+
+/// Decode WAV, FLAC, Ogg Vorbis, or MP3 bytes into samples
+#[cfg(feature = "audio_encode")]
+fn array_from_audio_bytes(bytes: &[u8], env: &Uiua) -> UiuaResult<Array<f64>> {
+    if bytes.starts_with(b"RIFF") {
+        array_from_wav_bytes(bytes, env)
+    } else {
+        array_from_compressed_audio_bytes(bytes, env)
+    }
+}
+
 #[cfg(feature = "audio_encode")]
 fn array_from_wav_bytes(bytes: &[u8], env: &Uiua) -> UiuaResult<Array<f64>> {
     let mut reader: WavReader<std::io::Cursor<&[u8]>> =
         WavReader::new(std::io::Cursor::new(bytes)).map_err(|e| env.error(e.to_string()))?;
     let spec = reader.spec();
     match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Int, 8) => {
+            array_from_wav_bytes_impl::<i8>(&mut reader, |i| i as f64 / i8::MAX as f64, env)
+        }
         (SampleFormat::Int, 16) => {
             array_from_wav_bytes_impl::<i16>(&mut reader, |i| i as f64 / i16::MAX as f64, env)
         }
+        (SampleFormat::Int, 24) => array_from_wav_bytes_impl::<i32>(
+            &mut reader,
+            |i| i as f64 / ((1 << 23) - 1) as f64,
+            env,
+        ),
         (SampleFormat::Int, 32) => {
             array_from_wav_bytes_impl::<i32>(&mut reader, |i| i as f64 / i32::MAX as f64, env)
         }
@@ -1793,6 +2583,77 @@ fn array_from_wav_bytes(bytes: &[u8], env: &Uiua) -> UiuaResult<Array<f64>> {
     }
 }
 
+/// Get the sample rate and bit depth of encoded audio
+///
+/// The bit depth of lossy formats is `0`.
+#[cfg(feature = "audio_encode")]
+fn audio_metadata(bytes: &[u8]) -> Result<(f64, f64), String> {
+    if bytes.starts_with(b"RIFF") {
+        let reader = WavReader::new(std::io::Cursor::new(bytes)).map_err(|e| e.to_string())?;
+        let spec = reader.spec();
+        return Ok((f64::from(spec.sample_rate), f64::from(spec.bits_per_sample)));
+    }
+    let reader = probe_compressed_audio(bytes)?;
+    let track = (reader.default_track()).ok_or("Audio does not contain any tracks")?;
+    let sample_rate = (track.codec_params.sample_rate).ok_or("Audio has no sample rate")?;
+    let bit_depth = track.codec_params.bits_per_sample.unwrap_or(0);
+    Ok((f64::from(sample_rate), f64::from(bit_depth)))
+}
+
+/// Open FLAC, Ogg Vorbis, or MP3 bytes for reading
+#[cfg(feature = "audio_encode")]
+fn probe_compressed_audio(
+    bytes: &[u8],
+) -> Result<Box<dyn symphonia::core::formats::FormatReader>, String> {
+    use symphonia::core::{
+        formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+    };
+    let source = MediaSourceStream::new(
+        Box::new(std::io::Cursor::new(bytes.to_vec())),
+        Default::default(),
+    );
+    let probed = symphonia::default::get_probe()
+        .format(
+            &Hint::new(),
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unsupported audio format: {e}"))?;
+    Ok(probed.format)
+}
+
+#[doc(hidden)]
+#[cfg(feature = "audio_encode")]
+pub fn wav_bytes_to_stereo(bytes: &[u8]) -> Result<Vec<[f64; 2]>, String> {
+    let mut reader = WavReader::new(std::io::Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let spec = reader.spec();
+    let samples: Vec<f64> = match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => (reader.samples::<f32>())
+            .map(|s| s.map(f64::from))
+            .collect::<Result<_, _>>(),
+        (SampleFormat::Int, bits) => {
+            let max = ((1i64 << (bits - 1)) - 1) as f64;
+            (reader.samples::<i32>())
+                .map(|s| s.map(|s| s as f64 / max))
+                .collect::<Result<_, _>>()
+        }
+        (sample_format, bits_per_sample) => {
+            return Err(format!(
+                "Unsupported sample format: {:?} {} bits per sample",
+                sample_format, bits_per_sample
+            ))
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(match spec.channels {
+        1 => samples.into_iter().map(|s| [s, s]).collect(),
+        n => (samples.chunks_exact(n as usize))
+            .map(|frame| [frame[0], frame[1]])
+            .collect(),
+    })
+}
+
 #[cfg(feature = "audio_encode")]
 fn array_from_wav_bytes_impl<T: hound::Sample>(
     reader: &mut WavReader<std::io::Cursor<&[u8]>>,
@@ -1815,22 +2676,203 @@ fn array_from_wav_bytes_impl<T: hound::Sample>(
     }
 }
 
+/// Decode FLAC, Ogg Vorbis, or MP3 bytes
+#[cfg(feature = "audio_encode")]
+fn array_from_compressed_audio_bytes(bytes: &[u8], env: &Uiua) -> UiuaResult<Array<f64>> {
+    use symphonia::core::{audio::SampleBuffer, codecs::DecoderOptions, errors::Error};
+    let mut reader = probe_compressed_audio(bytes).map_err(|e| env.error(e))?;
+    let track =
+        (reader.default_track()).ok_or_else(|| env.error("Audio does not contain any tracks"))?;
+    let track_id = track.id;
+    let frame_count = track.codec_params.n_frames;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| env.error(format!("Unsupported audio codec: {e}")))?;
+    let mut channels: Vec<ecow::EcoVec<f64>> = Vec::new();
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(env.error(format!("Failed to read audio: {e}"))),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Corrupt packets are skipped
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(env.error(format!("Failed to decode audio: {e}"))),
+        };
+        let spec = *decoded.spec();
+        let channel_count = spec.channels.count();
+        if channels.is_empty() {
+            channels = vec![ecow::EcoVec::new(); channel_count];
+        }
+        let mut buffer = SampleBuffer::<f64>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        for frame in buffer.samples().chunks_exact(channel_count) {
+            for (channel, &sample) in channels.iter_mut().zip(frame) {
+                channel.push(sample);
+            }
+        }
+    }
+    // Some encoders pad the last block
+    if let Some(frame_count) = frame_count {
+        for channel in &mut channels {
+            channel.truncate(frame_count as usize);
+        }
+    }
+    Ok(match channels.len() {
+        0 => Array::default(),
+        1 => channels.pop().unwrap().into(),
+        _ => Array::from_row_arrays(channels.into_iter().map(|ch| ch.into()), env)?,
+    })
+}
+
+/// How GIF frames are given their color palettes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GifPalette {
+    /// All frames share one palette
+    Global,
+    /// Each frame has its own palette
+    #[default]
+    PerFrame,
+}
+
+/// Options for encoding GIFs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GifOptions {
+    /// How many times to repeat the animation, or `None` to repeat forever
+    pub loop_count: Option<u16>,
+    /// Whether pixels with an alpha below 0.5 are transparent
+    pub transparent: bool,
+    /// How frames are given their color palettes
+    pub palette: GifPalette,
+    /// Whether to dither colors that are not in the palette
+    pub dither: bool,
+}
+
+impl FromStr for GifOptions {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = GifOptions::default();
+        for option in s.split_whitespace() {
+            let (name, arg) = option.split_once(':').unwrap_or((option, ""));
+            match (name, arg) {
+                ("loop", "inf") => options.loop_count = None,
+                ("loop", n) => {
+                    options.loop_count = Some(n.parse().map_err(|_| {
+                        format!("GIF loop count must be a natural number or inf, but it is {n:?}")
+                    })?)
+                }
+                ("transparent", "") => options.transparent = true,
+                ("palette", "global") => options.palette = GifPalette::Global,
+                ("palette", "frame") => options.palette = GifPalette::PerFrame,
+                ("dither", "") => options.dither = true,
+                _ => return Err(format!("Invalid GIF option {option:?}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
 #[doc(hidden)]
 #[cfg(feature = "gif")]
 pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, String> {
-    use std::collections::{HashMap, HashSet};
+    value_to_gif_bytes_with(
+        value,
+        &[frame_rate_delay(frame_rate)],
+        GifOptions::default(),
+    )
+}
+
+/// Get the frames of an animation and their delays in some unit
+///
+/// `delays` are in seconds. There must be one per frame, or a single one for all frames.
+#[cfg(any(feature = "gif", feature = "apng"))]
+fn animation_frames(
+    value: &Value,
+    delays: &[f64],
+    format: &str,
+    units_per_second: f64,
+) -> Result<(Vec<image::RgbaImage>, Vec<u16>), String> {
     if value.row_count() == 0 {
-        return Err("Cannot convert empty array into GIF".into());
+        return Err(format!("Cannot convert empty array into {format}"));
+    }
+    if delays.len() != 1 && delays.len() != value.row_count() {
+        return Err(format!(
+            "There must be 1 delay or 1 per frame, but there are \
+            {} delays and {} frames",
+            delays.len(),
+            value.row_count()
+        ));
+    }
+    if let Some(delay) = delays.iter().find(|d| !(d.is_finite() && **d >= 0.0)) {
+        return Err(format!(
+            "Delays must be non-negative numbers, but one is {delay}"
+        ));
     }
     let mut frames = Vec::with_capacity(value.row_count());
-    let mut width = 0;
-    let mut height = 0;
     for row in value.rows() {
-        let image = value_to_image(&row)?.into_rgb8();
-        width = image.width();
-        height = image.height();
-        frames.push(image);
-    }
+        frames.push(value_to_image(&row)?.into_rgba8());
+    }
+    // Delays are rounded relative to the start so that rounding errors do not accumulate
+    let mut time = 0.0;
+    let mut prev_end = 0.0;
+    let delays = (0..frames.len())
+        .map(|i| {
+            time += delays[if delays.len() == 1 { 0 } else { i }];
+            let end = (time * units_per_second).round();
+            let delay = (end - prev_end).min(u16::MAX as f64) as u16;
+            prev_end = end;
+            delay
+        })
+        .collect();
+    Ok((frames, delays))
+}
+
+/// Encode frames as an animated PNG
+///
+/// `delays` are in seconds. There must be one per frame, or a single one for all frames.
+#[doc(hidden)]
+#[cfg(feature = "apng")]
+pub fn value_to_apng_bytes(value: &Value, delays: &[f64]) -> Result<Vec<u8>, String> {
+    let (frames, delays) = animation_frames(value, delays, "APNG", 1000.0)?;
+    let (width, height) = frames[0].dimensions();
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|e| e.to_string())?;
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for (frame, delay) in frames.iter().zip(delays) {
+        writer
+            .set_frame_delay(delay, 1000)
+            .map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(frame.as_raw())
+            .map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Encode frames as a GIF
+///
+/// `delays` are in seconds. There must be one per frame, or a single one for all frames.
+#[doc(hidden)]
+#[cfg(feature = "gif")]
+pub fn value_to_gif_bytes_with(
+    value: &Value,
+    delays: &[f64],
+    options: GifOptions,
+) -> Result<Vec<u8>, String> {
+    use std::collections::HashMap;
+    let (frames, delays) = animation_frames(value, delays, "GIF", 100.0)?;
+    let (width, height) = frames[0].dimensions();
     if width > u16::MAX as u32 || height > u16::MAX as u32 {
         return Err(format!(
             "GIF dimensions must be at most {}x{}, but the frames are {}x{}",
@@ -1840,42 +2882,93 @@ pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, Str
             height
         ));
     }
-    let mut reduction = 1;
+    let is_opaque = |pixel: &image::Rgba<u8>| !options.transparent || pixel[3] >= 128;
+    let max_colors = if options.transparent { 255 } else { 256 };
+    let global = (options.palette == GifPalette::Global).then(|| {
+        let colors = frames.iter().flat_map(|frame| {
+            (frame.pixels())
+                .filter(|p| is_opaque(p))
+                .map(|p| [p[0], p[1], p[2]])
+        });
+        ColorQuantizer::new(colors, max_colors)
+    });
     let mut bytes = std::io::Cursor::new(Vec::new());
-    let mut all_colors = HashSet::new();
-    for frame in &frames {
-        for pixel in frame.pixels() {
-            all_colors.insert(pixel.0);
-        }
-    }
-    let mut used_colors = HashSet::new();
-    let used_colors = 'colors: loop {
-        used_colors.clear();
-        let adder = reduction - 1;
-        for color in &all_colors {
-            used_colors.insert(color.map(|p| p.saturating_add(adder) / reduction));
-            if used_colors.len() > 256 {
-                reduction += 1;
-                continue 'colors;
-            }
-        }
-        break used_colors;
-    };
-    let mut palette = Vec::with_capacity(used_colors.len() * 3);
-    let mut color_map: HashMap<[u8; 3], usize> = HashMap::new();
-    for color in used_colors {
-        color_map.insert(color, palette.len() / 3);
-        palette.extend(color);
-    }
-    let mut encoder = gif::Encoder::new(&mut bytes, width as u16, height as u16, &palette)
+    let global_palette = global.as_ref().map(|q| q.palette()).unwrap_or_default();
+    let mut encoder = gif::Encoder::new(&mut bytes, width as u16, height as u16, &global_palette)
         .map_err(|e| e.to_string())?;
-    const MIN_FRAME_RATE: f64 = 1.0 / 60.0;
-    let delay = ((1.0 / frame_rate.max(MIN_FRAME_RATE)).abs() * 100.0) as u16;
     encoder
-        .set_repeat(gif::Repeat::Infinite)
+        .set_repeat(match options.loop_count {
+            Some(n) => gif::Repeat::Finite(n),
+            None => gif::Repeat::Infinite,
+        })
         .map_err(|e| e.to_string())?;
-    for image in frames {
-        let mut frame = gif::Frame::from_rgb(width as u16, height as u16, image.as_raw());
+    for (image, delay) in frames.iter().zip(delays) {
+        let local;
+        let quantizer = match &global {
+            Some(quantizer) => quantizer,
+            None => {
+                let colors = (image.pixels())
+                    .filter(|p| is_opaque(p))
+                    .map(|p| [p[0], p[1], p[2]]);
+                local = ColorQuantizer::new(colors, max_colors);
+                &local
+            }
+        };
+        let transparent_index = options.transparent.then_some(quantizer.colors.len() as u8);
+        let mut indices = Vec::with_capacity((width * height) as usize);
+        let mut cache = HashMap::new();
+        // Floyd-Steinberg error for this row and the next
+        let mut errors = vec![[0f32; 3]; 2 * (width as usize + 2)];
+        for (y, row) in image.rows().enumerate() {
+            let (curr, next) = errors.split_at_mut(width as usize + 2);
+            if y > 0 {
+                curr.copy_from_slice(next);
+                next.fill([0.0; 3]);
+            }
+            for (x, pixel) in row.enumerate() {
+                if !is_opaque(pixel) {
+                    indices.push(transparent_index.unwrap());
+                    continue;
+                }
+                let mut color = [pixel[0], pixel[1], pixel[2]];
+                if options.dither {
+                    for (c, e) in color.iter_mut().zip(curr[x + 1]) {
+                        *c = (*c as f32 + e).round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+                let index = quantizer.index_of(color, &mut cache);
+                indices.push(index);
+                if options.dither {
+                    let chosen = quantizer.colors[index as usize];
+                    for c in 0..3 {
+                        let error = pixel[c] as f32 + curr[x + 1][c] - chosen[c] as f32;
+                        curr[x + 2][c] += error * 7.0 / 16.0;
+                        next[x][c] += error * 3.0 / 16.0;
+                        next[x + 1][c] += error * 5.0 / 16.0;
+                        next[x + 2][c] += error / 16.0;
+                    }
+                }
+            }
+        }
+        let mut frame = if global.is_some() {
+            gif::Frame::from_indexed_pixels(
+                width as u16,
+                height as u16,
+                &indices,
+                transparent_index,
+            )
+        } else {
+            gif::Frame::from_palette_pixels(
+                width as u16,
+                height as u16,
+                &indices,
+                &quantizer.palette(),
+                transparent_index,
+            )
+        };
+        if options.transparent {
+            frame.dispose = gif::DisposalMethod::Background;
+        }
         frame.delay = delay;
         encoder.write_frame(&frame).map_err(|e| e.to_string())?;
     }
@@ -1883,28 +2976,311 @@ pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, Str
     Ok(bytes.into_inner())
 }
 
+/// Get animation frame delays from a framerate or a list of delays
+#[cfg(any(feature = "gif", feature = "apng"))]
+fn frame_delays(delays: Value, env: &Uiua) -> UiuaResult<Vec<f64>> {
+    if delays.rank() == 0 {
+        let frame_rate = delays.as_num(env, "Framerate must be a number")?;
+        Ok(vec![frame_rate_delay(frame_rate)])
+    } else {
+        delays.as_nums(env, "Delays must be a list of numbers")
+    }
+}
+
+#[cfg(any(feature = "gif", feature = "apng"))]
+fn frame_rate_delay(frame_rate: f64) -> f64 {
+    const MIN_FRAME_RATE: f64 = 1.0 / 60.0;
+    1.0 / frame_rate.max(MIN_FRAME_RATE).abs()
+}
+
+/// Maps colors to indices in a palette of at most 256 colors
+#[cfg(any(feature = "gif", feature = "terminal_image"))]
+pub(crate) struct ColorQuantizer {
+    pub(crate) colors: Vec<[u8; 3]>,
+    neu_quant: Option<color_quant::NeuQuant>,
+}
+
+#[cfg(any(feature = "gif", feature = "terminal_image"))]
+impl ColorQuantizer {
+    pub(crate) fn new(colors: impl Iterator<Item = [u8; 3]>, max_colors: usize) -> Self {
+        let mut unique = std::collections::HashSet::new();
+        let mut rgba = Vec::new();
+        for color in colors {
+            unique.insert(color);
+            rgba.extend(color);
+            rgba.push(u8::MAX);
+        }
+        if unique.len() <= max_colors {
+            // Exact palette
+            let mut colors: Vec<_> = unique.into_iter().collect();
+            colors.sort_unstable();
+            if colors.is_empty() {
+                colors.push([0; 3]);
+            }
+            return ColorQuantizer {
+                colors,
+                neu_quant: None,
+            };
+        }
+        let neu_quant = color_quant::NeuQuant::new(10, max_colors, &rgba);
+        let colors = (neu_quant.color_map_rgb().chunks_exact(3))
+            .map(|c| [c[0], c[1], c[2]])
+            .collect();
+        ColorQuantizer {
+            colors,
+            neu_quant: Some(neu_quant),
+        }
+    }
+    #[cfg(feature = "gif")]
+    fn palette(&self) -> Vec<u8> {
+        let mut palette: Vec<u8> = self.colors.iter().flatten().copied().collect();
+        // A slot for the transparent color
+        if self.colors.len() < 256 {
+            palette.extend([0; 3]);
+        }
+        palette
+    }
+    pub(crate) fn index_of(
+        &self,
+        color: [u8; 3],
+        cache: &mut std::collections::HashMap<[u8; 3], u8>,
+    ) -> u8 {
+        *cache.entry(color).or_insert_with(|| {
+            if let Some(neu_quant) = &self.neu_quant {
+                return neu_quant.index_of(&[color[0], color[1], color[2], u8::MAX]) as u8;
+            }
+            let dist = |c: &[u8; 3]| -> u32 {
+                (c.iter().zip(color))
+                    .map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32)
+                    .sum()
+            };
+            (self.colors.iter().enumerate())
+                .min_by_key(|(_, c)| dist(c))
+                .map(|(i, _)| i as u8)
+                .unwrap()
+        })
+    }
+}
+
+/// Decode a GIF into its frames and the delay of each frame in seconds
 #[doc(hidden)]
 #[cfg(feature = "gif")]
-pub fn gif_bytes_to_value(bytes: &[u8]) -> Result<(f64, Value), gif::DecodingError> {
+pub fn gif_bytes_to_value(bytes: &[u8]) -> Result<(Value, Vec<f64>), String> {
     let mut decoder = gif::DecodeOptions::new();
     decoder.set_color_output(gif::ColorOutput::RGBA);
-    let mut decoder = decoder.read_info(bytes)?;
-    let first_frame = decoder.read_next_frame()?.unwrap();
-    let width = first_frame.width;
-    let height = first_frame.height;
+    let mut decoder = decoder.read_info(bytes).map_err(|e| e.to_string())?;
+    let width = decoder.width() as usize;
+    let height = decoder.height() as usize;
+    let mut canvas = vec![0u8; width * height * 4];
     let mut data: crate::cowslice::CowSlice<f64> = Default::default();
-    let mut frame_count = 1;
-    let mut delay_sum = first_frame.delay as f64 / 100.0;
-    data.extend(first_frame.buffer.iter().map(|&b| b as f64 / 255.0));
-    while let Some(frame) = decoder.read_next_frame()? {
-        data.extend(frame.buffer.iter().map(|&b| b as f64 / 255.0));
-        frame_count += 1;
-        delay_sum += frame.delay as f64 / 100.0;
-    }
-    let avg_delay = delay_sum / frame_count as f64;
-    let frame_rate = 1.0 / avg_delay;
-    let shape = crate::Shape::from_iter([frame_count, height as usize, width as usize, 4]);
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().map_err(|e| e.to_string())? {
+        let previous = (frame.dispose == gif::DisposalMethod::Previous).then(|| canvas.clone());
+        // Frames may only cover part of the canvas
+        let (left, top) = (frame.left as usize, frame.top as usize);
+        let frame_width = frame.width as usize;
+        for (i, pixel) in frame.buffer.chunks_exact(4).enumerate() {
+            let (x, y) = (left + i % frame_width, top + i / frame_width);
+            if x < width && y < height && pixel[3] != 0 {
+                canvas[(y * width + x) * 4..][..4].copy_from_slice(pixel);
+            }
+        }
+        data.extend(canvas.iter().map(|&b| b as f64 / 255.0));
+        delays.push(frame.delay as f64 / 100.0);
+        match frame.dispose {
+            gif::DisposalMethod::Background => {
+                for y in top..(top + frame.height as usize).min(height) {
+                    for x in left..(left + frame_width).min(width) {
+                        canvas[(y * width + x) * 4..][..4].fill(0);
+                    }
+                }
+            }
+            gif::DisposalMethod::Previous => canvas = previous.unwrap(),
+            _ => {}
+        }
+    }
+    if delays.is_empty() {
+        return Err("GIF has no frames".into());
+    }
+    let shape = crate::Shape::from_iter([delays.len(), height, width, 4]);
     let mut num = Value::Num(Array::new(shape, data));
     num.compress();
-    Ok((frame_rate, num))
+    Ok((num, delays))
+}
+
+#[cfg(feature = "midi")]
+const MIDI_TICKS_PER_BEAT: u16 = 480;
+
+#[doc(hidden)]
+#[cfg(feature = "midi")]
+pub fn notes_to_midi_bytes(notes: &Value) -> Result<Vec<u8>, String> {
+    use midly::{
+        num::{u15, u24, u28, u4, u7},
+        Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind,
+    };
+    let data: Vec<f64> = match notes {
+        Value::Num(nums) => nums.data.iter().copied().collect(),
+        #[cfg(feature = "bytes")]
+        Value::Byte(bytes) => bytes.data.iter().map(|&b| b as f64).collect(),
+        _ => return Err("MIDI notes must be a numeric array".into()),
+    };
+    match notes.shape().dims() {
+        [0] | [_, 5] => {}
+        _ => {
+            return Err(format!(
+                "MIDI notes must be a table with 5 columns, but its shape is {}",
+                notes.shape()
+            ))
+        }
+    }
+    // Collect note on and off events for each track
+    let mut tracks: Vec<Vec<(u32, bool, u8, u8)>> = Vec::new();
+    let ticks = |beats: f64, name: &str| -> Result<u32, String> {
+        let ticks = (beats * MIDI_TICKS_PER_BEAT as f64).round();
+        if !(0.0..=u28::max_value().as_int() as f64).contains(&ticks) {
+            return Err(format!("Note {name} {beats} is out of range"));
+        }
+        Ok(ticks as u32)
+    };
+    for (i, row) in data.chunks_exact(5).enumerate() {
+        let &[track, time, pitch, velocity, duration] = row else {
+            unreachable!()
+        };
+        if track < 0.0 || track.fract() != 0.0 || track > u16::MAX as f64 {
+            return Err(format!("Track must be a natural number, but it is {track}"));
+        }
+        if !(0.0..=127.0).contains(&pitch) || pitch.fract() != 0.0 {
+            return Err(format!(
+                "Pitch must be an integer between 0 and 127, but it is {pitch}"
+            ));
+        }
+        if !(1.0..=127.0).contains(&velocity) || velocity.fract() != 0.0 {
+            return Err(format!(
+                "Velocity must be an integer between 1 and 127, but it is {velocity}"
+            ));
+        }
+        if duration < 0.0 {
+            return Err(format!(
+                "Duration of note {i} must be non-negative, but it is {duration}"
+            ));
+        }
+        let start = ticks(time, "time")?;
+        // Notes last at least 1 tick so that their note off comes after their note on
+        let end = ticks(time + duration, "end time")?.max(start + 1);
+        let track = track as usize;
+        if tracks.len() <= track {
+            tracks.resize(track + 1, Vec::new());
+        }
+        tracks[track].push((start, true, pitch as u8, velocity as u8));
+        tracks[track].push((end, false, pitch as u8, 0));
+    }
+    if tracks.is_empty() {
+        tracks.push(Vec::new());
+    }
+    let format = if tracks.len() == 1 {
+        Format::SingleTrack
+    } else {
+        Format::Parallel
+    };
+    let timing = Timing::Metrical(u15::new(MIDI_TICKS_PER_BEAT));
+    let mut smf = Smf::new(Header::new(format, timing));
+    for (i, mut events) in tracks.into_iter().enumerate() {
+        // Note offs come before note ons at the same time
+        events.sort_by_key(|&(tick, on, ..)| (tick, on));
+        let channel = u4::new((i % 16) as u8);
+        let mut track = Vec::with_capacity(events.len() + 2);
+        if i == 0 {
+            track.push(TrackEvent {
+                delta: 0.into(),
+                kind: TrackEventKind::Meta(MetaMessage::Tempo(u24::new(500_000))),
+            });
+        }
+        let mut prev = 0;
+        for (tick, on, key, vel) in events {
+            let key = u7::new(key);
+            let message = if on {
+                MidiMessage::NoteOn {
+                    key,
+                    vel: u7::new(vel),
+                }
+            } else {
+                MidiMessage::NoteOff {
+                    key,
+                    vel: u7::new(0),
+                }
+            };
+            track.push(TrackEvent {
+                delta: u28::new(tick - prev),
+                kind: TrackEventKind::Midi { channel, message },
+            });
+            prev = tick;
+        }
+        track.push(TrackEvent {
+            delta: 0.into(),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        });
+        smf.tracks.push(track);
+    }
+    let mut bytes = Vec::new();
+    smf.write_std(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+#[doc(hidden)]
+#[cfg(feature = "midi")]
+pub fn midi_bytes_to_notes(bytes: &[u8]) -> Result<Array<f64>, String> {
+    use midly::{MidiMessage, Smf, Timing, TrackEventKind};
+    use std::collections::HashMap;
+    let smf = Smf::parse(bytes).map_err(|e| format!("Failed to decode MIDI: {e}"))?;
+    let ticks_per_beat = match smf.header.timing {
+        Timing::Metrical(ticks) => ticks.as_int() as f64,
+        Timing::Timecode(..) => return Err("MIDI with timecode timing is not supported".into()),
+    };
+    let mut notes: Vec<[f64; 5]> = Vec::new();
+    for (i, track) in smf.tracks.iter().enumerate() {
+        let mut tick = 0u64;
+        // Start times and velocities of held notes by channel and key
+        let mut held: HashMap<(u8, u8), Vec<(u64, u8)>> = HashMap::new();
+        for event in track {
+            tick += event.delta.as_int() as u64;
+            let TrackEventKind::Midi { channel, message } = event.kind else {
+                continue;
+            };
+            let (key, vel) = match message {
+                MidiMessage::NoteOn { key, vel } => (key.as_int(), vel.as_int()),
+                MidiMessage::NoteOff { key, .. } => (key.as_int(), 0),
+                _ => continue,
+            };
+            let starts = held.entry((channel.as_int(), key)).or_default();
+            if vel > 0 {
+                starts.push((tick, vel));
+            } else if !starts.is_empty() {
+                let (start, vel) = starts.remove(0);
+                notes.push([
+                    i as f64,
+                    start as f64 / ticks_per_beat,
+                    key as f64,
+                    vel as f64,
+                    (tick - start) as f64 / ticks_per_beat,
+                ]);
+            }
+        }
+        // Notes that are never released end with the track
+        for ((_, key), starts) in held {
+            for (start, vel) in starts {
+                notes.push([
+                    i as f64,
+                    start as f64 / ticks_per_beat,
+                    key as f64,
+                    vel as f64,
+                    (tick - start) as f64 / ticks_per_beat,
+                ]);
+            }
+        }
+    }
+    notes.sort_by(|a, b| a[..3].partial_cmp(&b[..3]).unwrap());
+    let shape = crate::Shape::from_iter([notes.len(), 5]);
+    let mut data: crate::cowslice::CowSlice<f64> = Default::default();
+    data.extend(notes.into_iter().flatten());
+    Ok(Array::new(shape, data))
 }
@@ -12,9 +12,10 @@ use std::{
     time::Duration,
 };
 
-use crate::{Handle, SysBackend};
+use crate::{Array, Handle, SysBackend};
 use bufreaderwriter::seq::BufReaderWriterSeq;
 use dashmap::DashMap;
+use ecow::EcoVec;
 use once_cell::sync::Lazy;
 
 /// The defualt native system backend
@@ -33,9 +34,77 @@ struct GlobalNativeSys {
     audio_stream_time: parking_lot::Mutex<Option<f64>>,
     #[cfg(feature = "audio")]
     audio_time_socket: parking_lot::Mutex<Option<std::sync::Arc<std::net::UdpSocket>>>,
+    audio_input: parking_lot::Mutex<Option<AudioInput>>,
     colored_errors: DashMap<String, String>,
 }
 
+/// A source of audio input
+enum AudioInput {
+    #[cfg(feature = "audio_encode")]
+    Wav(hound::WavReader<std::io::BufReader<File>>),
+    /// Interleaved 16-bit little-endian PCM
+    Stdin { channels: usize, sample_rate: u32 },
+}
+
+impl AudioInput {
+    fn channels(&self) -> usize {
+        match self {
+            #[cfg(feature = "audio_encode")]
+            AudioInput::Wav(reader) => reader.spec().channels as usize,
+            AudioInput::Stdin { channels, .. } => *channels,
+        }
+    }
+    fn sample_rate(&self) -> u32 {
+        match self {
+            #[cfg(feature = "audio_encode")]
+            AudioInput::Wav(reader) => reader.spec().sample_rate,
+            AudioInput::Stdin { sample_rate, .. } => *sample_rate,
+        }
+    }
+    /// Read at most `count` frames of interleaved samples
+    fn read(&mut self, count: usize) -> Result<Vec<f64>, String> {
+        let sample_count = count * self.channels();
+        match self {
+            #[cfg(feature = "audio_encode")]
+            AudioInput::Wav(reader) => {
+                let spec = reader.spec();
+                match spec.sample_format {
+                    hound::SampleFormat::Float => (reader.samples::<f32>())
+                        .take(sample_count)
+                        .map(|s| s.map(f64::from))
+                        .collect::<Result<_, _>>(),
+                    hound::SampleFormat::Int => {
+                        let max = ((1i64 << (spec.bits_per_sample - 1)) - 1) as f64;
+                        (reader.samples::<i32>())
+                            .take(sample_count)
+                            .map(|s| s.map(|s| s as f64 / max))
+                            .collect()
+                    }
+                }
+                .map_err(|e| format!("Failed to read audio input: {e}"))
+            }
+            AudioInput::Stdin { channels, .. } => {
+                let mut bytes = vec![0; sample_count * 2];
+                let mut len = 0;
+                let mut stdin = stdin().lock();
+                while len < bytes.len() {
+                    match stdin.read(&mut bytes[len..]) {
+                        Ok(0) => break,
+                        Ok(n) => len += n,
+                        Err(e) => return Err(format!("Failed to read audio input: {e}")),
+                    }
+                }
+                // Drop any incomplete frame
+                bytes.truncate(len - len % (*channels * 2));
+                Ok(bytes
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]) as f64 / i16::MAX as f64)
+                    .collect())
+            }
+        }
+    }
+}
+
 enum SysStream<'a> {
     File(dashmap::mapref::one::RefMut<'a, Handle, Buffered<File>>),
     TcpListener(dashmap::mapref::one::RefMut<'a, Handle, TcpListener>),
@@ -54,6 +123,7 @@ impl Default for GlobalNativeSys {
             audio_stream_time: parking_lot::Mutex::new(None),
             #[cfg(feature = "audio")]
             audio_time_socket: parking_lot::Mutex::new(None),
+            audio_input: parking_lot::Mutex::new(None),
             colored_errors: DashMap::new(),
         }
     }
@@ -102,6 +172,33 @@ pub fn set_audio_stream_time_port(port: u16) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Set the source of audio input
+///
+/// `-` reads interleaved 16-bit little-endian PCM from stdin with the given channel count and sample rate.
+/// Any other path is read as a WAV file.
+#[cfg(feature = "binary")]
+#[doc(hidden)]
+pub fn set_audio_input(path: &str, channels: usize, sample_rate: u32) -> Result<(), String> {
+    let input = if path == "-" {
+        if channels == 0 {
+            return Err("Audio input must have at least 1 channel".into());
+        }
+        AudioInput::Stdin {
+            channels,
+            sample_rate,
+        }
+    } else {
+        #[cfg(feature = "audio_encode")]
+        {
+            AudioInput::Wav(hound::WavReader::open(path).map_err(|e| e.to_string())?)
+        }
+        #[cfg(not(feature = "audio_encode"))]
+        return Err("WAV audio input is not supported in this environment".into());
+    };
+    *NATIVE_SYS.audio_input.lock() = Some(input);
+    Ok(())
+}
+
 impl SysBackend for NativeSys {
     fn any(&self) -> &dyn Any {
         self
@@ -248,34 +345,24 @@ impl SysBackend for NativeSys {
         sleep(Duration::from_secs_f64(seconds));
         Ok(())
     }
-    #[cfg(all(feature = "terminal_image", feature = "image"))]
+    #[cfg(feature = "terminal_image")]
     fn show_image(&self, image: image::DynamicImage) -> Result<(), String> {
-        let (width, height) = if let Some((w, h)) = term_size::dimensions() {
-            let (tw, th) = (w as u32, h.saturating_sub(1) as u32);
-            let (iw, ih) = (image.width(), image.height() / 2);
-            let scaled_to_height = (iw * th / ih.max(1), th);
-            let scaled_to_width = (tw, ih * tw / iw.max(1));
-            let (w, h) = if scaled_to_height.0 <= tw {
-                scaled_to_height
-            } else {
-                scaled_to_width
-            };
-            (Some(w), Some(h))
-        } else {
-            (None, None)
-        };
-        viuer::print(
-            &image,
-            &viuer::Config {
-                width,
-                height,
-                absolute_offset: false,
-                transparent: true,
-                ..Default::default()
-            },
-        )
-        .map(drop)
-        .map_err(|e| format!("Failed to show image: {e}"))
+        crate::term_image::print_image(&image, None).map(drop)
+    }
+    #[cfg(all(feature = "terminal_image", feature = "gif"))]
+    fn show_gif(&self, gif_bytes: Vec<u8>) -> Result<(), String> {
+        let (frames, delays) = crate::gif_bytes_to_value(&gif_bytes)?;
+        let id = std::process::id();
+        for (i, (frame, delay)) in frames.into_rows().zip(&delays).enumerate() {
+            let image = crate::value_to_image(&frame)?;
+            let rows = crate::term_image::print_image(&image, Some(id))?;
+            sleep(Duration::from_secs_f64(*delay));
+            // Draw the next frame over this one
+            if i + 1 < delays.len() {
+                print!("\x1b[{rows}A\r");
+            }
+        }
+        Ok(())
     }
     #[cfg(feature = "audio")]
     fn play_audio(&self, wav_bytes: Vec<u8>) -> Result<(), String> {
@@ -329,6 +416,7 @@ impl SysBackend for NativeSys {
                     }
                 }
                 match (self.f)(&times) {
+                    Ok(samples) if samples.is_empty() => None,
                     Ok(samples) => {
                         self.samples = samples.into_iter();
                         self.next(sample_rate)
@@ -354,6 +442,32 @@ impl SysBackend for NativeSys {
             Err(e) => Err(format!("Failed to initialize audio output stream: {e}").to_string()),
         }
     }
+    fn audio_input(&self, count: usize) -> Result<Array<f64>, String> {
+        let mut input = NATIVE_SYS.audio_input.lock();
+        let input = input.as_mut().ok_or(
+            "No audio input was given. \
+            Use `uiua run --audio-input <path>` to read from a WAV file or stdin.",
+        )?;
+        let channels = input.channels();
+        let interleaved = input.read(count)?;
+        if channels == 1 {
+            return Ok(interleaved.into_iter().collect());
+        }
+        let frames = interleaved.len() / channels;
+        let mut data = EcoVec::with_capacity(interleaved.len());
+        for c in 0..channels {
+            for f in 0..frames {
+                data.push(interleaved[f * channels + c]);
+            }
+        }
+        Ok(Array::new([channels, frames].as_slice(), data))
+    }
+    fn audio_input_sample_rate(&self) -> u32 {
+        match &*NATIVE_SYS.audio_input.lock() {
+            Some(input) => input.sample_rate(),
+            None => self.audio_sample_rate(),
+        }
+    }
     fn tcp_listen(&self, addr: &str) -> Result<Handle, String> {
         let handle = NATIVE_SYS.new_handle();
         let listener = TcpListener::bind(addr).map_err(|e| e.to_string())?;
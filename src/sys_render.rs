@@ -0,0 +1,300 @@
+//! A system backend for rendering audio without an audio device
+
+use std::{any::Any, path::Path, sync::Arc, time::Duration};
+
+#[cfg(feature = "image")]
+use image::DynamicImage;
+use parking_lot::Mutex;
+
+use crate::{Array, AudioStreamFn, FfiType, Handle, IntoSysBackend, SysBackend, UiuaResult, Value};
+
+/// A system backend that renders audio into memory instead of playing it
+///
+/// Audio passed to [`&ap`](crate::SysOp::AudioPlay) is appended as is.
+/// Audio streamed with [`&ast`](crate::SysOp::AudioStream) is rendered for a fixed duration.
+///
+/// All other operations are forwarded to the wrapped backend.
+pub struct AudioRenderSys {
+    inner: Arc<dyn SysBackend>,
+    sample_rate: u32,
+    duration: f64,
+    samples: Mutex<Vec<[f64; 2]>>,
+}
+
+impl AudioRenderSys {
+    /// Create a new audio rendering backend
+    ///
+    /// `duration` is the number of seconds to render each audio stream for.
+    pub fn new(inner: impl IntoSysBackend, sample_rate: u32, duration: f64) -> Self {
+        Self {
+            inner: inner.into_sys_backend(),
+            sample_rate,
+            duration,
+            samples: Mutex::new(Vec::new()),
+        }
+    }
+    /// Get the rendered stereo samples
+    pub fn samples(&self) -> Vec<[f64; 2]> {
+        self.samples.lock().clone()
+    }
+    /// Encode the rendered samples as WAV bytes
+    #[cfg(feature = "audio_encode")]
+    pub fn wav_bytes(&self) -> Result<Vec<u8>, String> {
+        crate::stereo_to_wave_bytes(
+            &self.samples.lock(),
+            |s| (s * i16::MAX as f64) as i16,
+            16,
+            hound::SampleFormat::Int,
+            self.sample_rate,
+        )
+    }
+}
+
+/// Render an audio stream function for some number of seconds
+pub fn render_audio_stream(
+    f: &mut AudioStreamFn,
+    sample_rate: u32,
+    duration: f64,
+) -> UiuaResult<Vec<[f64; 2]>> {
+    const SAMPLES_PER_FRAME: usize = 10000;
+    let len = (duration.max(0.0) * sample_rate as f64).round() as usize;
+    let mut samples = Vec::with_capacity(len);
+    let mut times = Vec::with_capacity(SAMPLES_PER_FRAME);
+    while samples.len() < len {
+        times.clear();
+        let start = samples.len();
+        for i in start..(start + SAMPLES_PER_FRAME).min(len) {
+            times.push(i as f64 / sample_rate as f64);
+        }
+        let frame = f(&times)?;
+        if frame.is_empty() {
+            break;
+        }
+        samples.extend(frame);
+    }
+    samples.truncate(len);
+    Ok(samples)
+}
+
+impl SysBackend for AudioRenderSys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    #[cfg(feature = "audio_encode")]
+    fn play_audio(&self, wav_bytes: Vec<u8>) -> Result<(), String> {
+        let samples = crate::wav_bytes_to_stereo(&wav_bytes)?;
+        self.samples.lock().extend(samples);
+        Ok(())
+    }
+    fn audio_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    fn stream_audio(&self, mut f: AudioStreamFn) -> Result<(), String> {
+        let samples = render_audio_stream(&mut f, self.sample_rate, self.duration)
+            .map_err(|e| e.to_string())?;
+        self.samples.lock().extend(samples);
+        Ok(())
+    }
+    fn audio_input(&self, count: usize) -> Result<Array<f64>, String> {
+        self.inner.audio_input(count)
+    }
+    fn audio_input_sample_rate(&self) -> u32 {
+        self.inner.audio_input_sample_rate()
+    }
+    fn save_error_color(&self, message: String, colored: String) {
+        self.inner.save_error_color(message, colored)
+    }
+    fn print_str_stdout(&self, s: &str) -> Result<(), String> {
+        self.inner.print_str_stdout(s)
+    }
+    fn print_str_stderr(&self, s: &str) -> Result<(), String> {
+        self.inner.print_str_stderr(s)
+    }
+    fn print_str_trace(&self, s: &str) {
+        self.inner.print_str_trace(s)
+    }
+    fn scan_line_stdin(&self) -> Result<Option<String>, String> {
+        self.inner.scan_line_stdin()
+    }
+    fn set_raw_mode(&self, raw_mode: bool) -> Result<(), String> {
+        self.inner.set_raw_mode(raw_mode)
+    }
+    fn var(&self, name: &str) -> Option<String> {
+        self.inner.var(name)
+    }
+    fn term_size(&self) -> Result<(usize, usize), String> {
+        self.inner.term_size()
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        self.inner.file_exists(path)
+    }
+    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        self.inner.list_dir(path)
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        self.inner.is_file(path)
+    }
+    fn delete(&self, path: &str) -> Result<(), String> {
+        self.inner.delete(path)
+    }
+    fn trash(&self, path: &str) -> Result<(), String> {
+        self.inner.trash(path)
+    }
+    fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
+        self.inner.read(handle, count)
+    }
+    fn read_until(&self, handle: Handle, delim: &[u8]) -> Result<Vec<u8>, String> {
+        self.inner.read_until(handle, delim)
+    }
+    fn write(&self, handle: Handle, contents: &[u8]) -> Result<(), String> {
+        self.inner.write(handle, contents)
+    }
+    fn create_file(&self, path: &Path) -> Result<Handle, String> {
+        self.inner.create_file(path)
+    }
+    fn open_file(&self, path: &Path) -> Result<Handle, String> {
+        self.inner.open_file(path)
+    }
+    fn file_read_all(&self, path: &Path) -> Result<Vec<u8>, String> {
+        self.inner.file_read_all(path)
+    }
+    fn file_write_all(&self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        self.inner.file_write_all(path, contents)
+    }
+    fn sleep(&self, seconds: f64) -> Result<(), String> {
+        self.inner.sleep(seconds)
+    }
+    #[cfg(feature = "image")]
+    fn show_image(&self, image: DynamicImage) -> Result<(), String> {
+        self.inner.show_image(image)
+    }
+    fn show_gif(&self, gif_bytes: Vec<u8>) -> Result<(), String> {
+        self.inner.show_gif(gif_bytes)
+    }
+    fn tcp_listen(&self, addr: &str) -> Result<Handle, String> {
+        self.inner.tcp_listen(addr)
+    }
+    fn tcp_accept(&self, handle: Handle) -> Result<Handle, String> {
+        self.inner.tcp_accept(handle)
+    }
+    fn tcp_connect(&self, addr: &str) -> Result<Handle, String> {
+        self.inner.tcp_connect(addr)
+    }
+    fn tcp_addr(&self, handle: Handle) -> Result<String, String> {
+        self.inner.tcp_addr(handle)
+    }
+    fn tcp_set_non_blocking(&self, handle: Handle, non_blocking: bool) -> Result<(), String> {
+        self.inner.tcp_set_non_blocking(handle, non_blocking)
+    }
+    fn tcp_set_read_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        self.inner.tcp_set_read_timeout(handle, timeout)
+    }
+    fn tcp_set_write_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        self.inner.tcp_set_write_timeout(handle, timeout)
+    }
+    fn close(&self, handle: Handle) -> Result<(), String> {
+        self.inner.close(handle)
+    }
+    fn invoke(&self, path: &str) -> Result<(), String> {
+        self.inner.invoke(path)
+    }
+    fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<i32, String> {
+        self.inner.run_command_inherit(command, args)
+    }
+    fn run_command_capture(
+        &self,
+        command: &str,
+        args: &[&str],
+    ) -> Result<(i32, String, String), String> {
+        self.inner.run_command_capture(command, args)
+    }
+    fn change_directory(&self, path: &str) -> Result<(), String> {
+        self.inner.change_directory(path)
+    }
+    fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
+        self.inner.https_get(request, handle)
+    }
+    fn ffi(
+        &self,
+        file: &str,
+        result_ty: FfiType,
+        name: &str,
+        arg_tys: &[FfiType],
+        args: &[Value],
+    ) -> Result<Value, String> {
+        self.inner.ffi(file, result_ty, name, arg_tys, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SafeSys, Uiua};
+
+    #[test]
+    fn render_stream() {
+        let mut env = Uiua::with_backend(AudioRenderSys::new(SafeSys, 1000, 2.5));
+        env.run_str("&ast(×0.5○×τ×100)").unwrap();
+        let samples = env.downcast_backend::<AudioRenderSys>().unwrap().samples();
+        assert_eq!(samples.len(), 2500);
+        let expected = 0.5 * (0.2 * std::f64::consts::PI).sin();
+        assert!((samples[1001][0] - expected).abs() < 1e-9);
+        assert_eq!(samples[1001][0], samples[1001][1]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "native_sys", feature = "binary", feature = "audio_encode"))]
+    fn wav_input() {
+        use crate::{set_audio_input, NativeSys};
+
+        let path = std::env::temp_dir().join("uiua_test_audio_input.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 1000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..5 {
+            writer.write_sample(i as i16 * 1000).unwrap();
+            writer.write_sample(-(i as i16) * 1000).unwrap();
+        }
+        writer.finalize().unwrap();
+        set_audio_input(path.to_str().unwrap(), 0, 0).unwrap();
+
+        let mut env = Uiua::with_backend(AudioRenderSys::new(NativeSys, 1000, 1.0));
+        env.run_str("&air &ai 3").unwrap();
+        let rate = env.pop_num().unwrap();
+        let chunk = env.pop("chunk").unwrap();
+        assert_eq!(rate, 1000.0);
+        assert_eq!(chunk.shape().dims(), [2, 3]);
+        let max = i16::MAX as f64;
+        let expected: Vec<f64> = [0, 1000, 2000, 0, -1000, -2000]
+            .into_iter()
+            .map(|s| s as f64 / max)
+            .collect();
+        assert_eq!(chunk.as_num_array().unwrap().data.as_slice(), expected);
+
+        // The remaining frames pass through unchanged and the stream ends with the input
+        env.run_str("&ais∘").unwrap();
+        let samples = env.downcast_backend::<AudioRenderSys>().unwrap().samples();
+        assert_eq!(
+            samples,
+            [[3000.0 / max, -3000.0 / max], [4000.0 / max, -4000.0 / max]]
+        );
+        env.run_str("&ai 3").unwrap();
+        assert_eq!(env.pop("chunk").unwrap().shape().dims(), [2, 0]);
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,313 @@
+//! Showing images in the terminal
+
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Write as _},
+    io::{stdout, Write},
+    str::FromStr,
+};
+
+use base64::Engine;
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use parking_lot::Mutex;
+
+use crate::{image_to_bytes, ColorQuantizer};
+
+/// A protocol for showing images in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageProtocol {
+    /// Detect the best protocol the terminal supports
+    #[default]
+    Auto,
+    /// The Kitty graphics protocol
+    Kitty,
+    /// Sixel graphics
+    Sixel,
+    /// Colored half-block characters, which work in any terminal with color
+    HalfBlock,
+}
+
+impl FromStr for ImageProtocol {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ImageProtocol::Auto),
+            "kitty" => Ok(ImageProtocol::Kitty),
+            "sixel" => Ok(ImageProtocol::Sixel),
+            "halfblock" => Ok(ImageProtocol::HalfBlock),
+            _ => Err(format!("unknown image protocol `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for ImageProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageProtocol::Auto => write!(f, "auto"),
+            ImageProtocol::Kitty => write!(f, "kitty"),
+            ImageProtocol::Sixel => write!(f, "sixel"),
+            ImageProtocol::HalfBlock => write!(f, "halfblock"),
+        }
+    }
+}
+
+static IMAGE_PROTOCOL: Mutex<ImageProtocol> = Mutex::new(ImageProtocol::Auto);
+
+/// The environment variable that overrides the terminal image protocol
+pub const IMAGE_PROTOCOL_VAR: &str = "UIUA_IMAGE_PROTOCOL";
+
+/// Set the protocol used to show images in the terminal
+///
+/// [`ImageProtocol::Auto`] defers to the `UIUA_IMAGE_PROTOCOL` environment variable, then to detection.
+pub fn set_image_protocol(protocol: ImageProtocol) {
+    *IMAGE_PROTOCOL.lock() = protocol;
+}
+
+/// Get the protocol used to show images in the terminal
+///
+/// This is never [`ImageProtocol::Auto`].
+pub fn image_protocol() -> ImageProtocol {
+    let protocol = *IMAGE_PROTOCOL.lock();
+    if protocol != ImageProtocol::Auto {
+        return protocol;
+    }
+    if let Some(protocol) = env::var(IMAGE_PROTOCOL_VAR)
+        .ok()
+        .and_then(|var| var.parse().ok())
+        .filter(|&p| p != ImageProtocol::Auto)
+    {
+        return protocol;
+    }
+    detect_image_protocol()
+}
+
+/// Detect the image protocol from the environment of well-known terminals
+fn detect_image_protocol() -> ImageProtocol {
+    let term = env::var("TERM").unwrap_or_default();
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+    if env::var_os("KITTY_WINDOW_ID").is_some()
+        || term.contains("kitty")
+        || term.contains("ghostty")
+        || ["WezTerm", "ghostty"].contains(&term_program.as_str())
+    {
+        ImageProtocol::Kitty
+    } else if term.contains("sixel")
+        || ["foot", "mlterm", "contour", "yaft"]
+            .iter()
+            .any(|name| term.starts_with(name))
+        || ["iTerm.app", "mintty"].contains(&term_program.as_str())
+    {
+        ImageProtocol::Sixel
+    } else {
+        ImageProtocol::HalfBlock
+    }
+}
+
+/// The size of the terminal in cells and the size of a cell in pixels
+struct TermSize {
+    cols: u32,
+    rows: u32,
+    cell_width: u32,
+    cell_height: u32,
+}
+
+impl TermSize {
+    fn get() -> Self {
+        let size = crossterm::terminal::window_size().ok();
+        let (cols, rows) = (size.as_ref())
+            .filter(|size| size.columns > 0 && size.rows > 0)
+            .map(|size| (size.columns as u32, size.rows as u32))
+            .or_else(|| term_size::dimensions().map(|(w, h)| (w as u32, h as u32)))
+            .unwrap_or((80, 24));
+        // Assume a common cell size if the terminal does not report its size in pixels
+        let (cell_width, cell_height) = size
+            .filter(|size| size.width > 0 && size.height > 0)
+            .map(|size| (size.width as u32 / cols, size.height as u32 / rows))
+            .filter(|&(w, h)| w > 0 && h > 0)
+            .unwrap_or((8, 16));
+        TermSize {
+            cols,
+            rows,
+            cell_width,
+            cell_height,
+        }
+    }
+    /// The largest size an image can be shown at, in pixels
+    fn max_pixels(&self) -> (u32, u32) {
+        (
+            self.cols * self.cell_width,
+            self.rows.saturating_sub(1).max(1) * self.cell_height,
+        )
+    }
+}
+
+/// Print an image to the terminal
+///
+/// `id` identifies the image for protocols that can replace a previously shown image.
+/// Returns the number of rows the image takes up.
+pub(crate) fn print_image(image: &DynamicImage, id: Option<u32>) -> Result<u32, String> {
+    match image_protocol() {
+        ImageProtocol::Kitty => print_kitty(image, id),
+        ImageProtocol::Sixel => print_sixel(image),
+        ImageProtocol::Auto | ImageProtocol::HalfBlock => print_half_block(image),
+    }
+}
+
+fn print_kitty(image: &DynamicImage, id: Option<u32>) -> Result<u32, String> {
+    let size = TermSize::get();
+    let (max_width, max_height) = size.max_pixels();
+    let (width, height) = image.dimensions();
+    // Scale down in the terminal instead of resampling
+    let scale = (max_width as f64 / width.max(1) as f64)
+        .min(max_height as f64 / height.max(1) as f64)
+        .min(1.0);
+    let cols = ((width as f64 * scale / size.cell_width as f64).ceil() as u32).max(1);
+    let rows = ((height as f64 * scale / size.cell_height as f64).ceil() as u32).max(1);
+    let png = image_to_bytes(image, image::ImageOutputFormat::Png)?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+    let mut out = String::new();
+    let chunks: Vec<&str> = (encoded.as_bytes().chunks(4096))
+        .map(|chunk| std::str::from_utf8(chunk).unwrap())
+        .collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            out.push_str("\x1b_Ga=T,f=100,q=2");
+            if let Some(id) = id {
+                write!(out, ",i={id},p=1").unwrap();
+            }
+            if scale < 1.0 {
+                write!(out, ",c={cols},r={rows}").unwrap();
+            }
+            write!(out, ",m={more};{chunk}\x1b\\").unwrap();
+        } else {
+            write!(out, "\x1b_Gm={more};{chunk}\x1b\\").unwrap();
+        }
+    }
+    out.push('\n');
+    write_stdout(&out)?;
+    Ok(rows)
+}
+
+fn print_sixel(image: &DynamicImage) -> Result<u32, String> {
+    let size = TermSize::get();
+    let (max_width, max_height) = size.max_pixels();
+    let (width, height) = image.dimensions();
+    let image = if width > max_width || height > max_height {
+        image.resize(max_width, max_height, FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+    let image = image.into_rgba8();
+    let (width, height) = image.dimensions();
+    let is_opaque = |p: &image::Rgba<u8>| p[3] >= 128;
+    let quantizer = ColorQuantizer::new(
+        (image.pixels())
+            .filter(|p| is_opaque(p))
+            .map(|p| [p[0], p[1], p[2]]),
+        256,
+    );
+    let mut cache = HashMap::new();
+    let indices: Vec<Option<u8>> = (image.pixels())
+        .map(|p| is_opaque(p).then(|| quantizer.index_of([p[0], p[1], p[2]], &mut cache)))
+        .collect();
+    // Transparent pixels are left unpainted
+    let mut out = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+    for (i, color) in quantizer.colors.iter().enumerate() {
+        let [r, g, b] = color.map(|c| c as u32 * 100 / 255);
+        write!(out, "#{i};2;{r};{g};{b}").unwrap();
+    }
+    let (width, height) = (width as usize, height as usize);
+    for band in (0..height).step_by(6) {
+        let band_height = 6.min(height - band);
+        let mut colors: Vec<u8> = (band..band + band_height)
+            .flat_map(|y| &indices[y * width..(y + 1) * width])
+            .flatten()
+            .copied()
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for (c, &color) in colors.iter().enumerate() {
+            if c > 0 {
+                out.push('$');
+            }
+            write!(out, "#{color}").unwrap();
+            let sixels = (0..width).map(|x| {
+                let bits = (0..band_height)
+                    .filter(|dy| indices[(band + dy) * width + x] == Some(color))
+                    .fold(0, |bits, dy| bits | 1 << dy);
+                (63 + bits) as u8 as char
+            });
+            push_run_length(&mut out, sixels);
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\\n");
+    write_stdout(&out)?;
+    Ok((height as u32).div_ceil(size.cell_height))
+}
+
+/// Push sixel characters, compressing runs
+fn push_run_length(out: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |out: &mut String, (c, n): (char, usize)| {
+        if n > 3 {
+            write!(out, "!{n}{c}").unwrap();
+        } else {
+            out.extend(std::iter::repeat_n(c, n));
+        }
+    };
+    for c in sixels {
+        match &mut run {
+            Some((prev, n)) if *prev == c => *n += 1,
+            _ => {
+                if let Some(run) = run {
+                    flush(out, run);
+                }
+                run = Some((c, 1));
+            }
+        }
+    }
+    if let Some(run) = run {
+        flush(out, run);
+    }
+}
+
+fn print_half_block(image: &DynamicImage) -> Result<u32, String> {
+    let (width, height) = if let Some((w, h)) = term_size::dimensions() {
+        let (tw, th) = (w as u32, h.saturating_sub(1) as u32);
+        let (iw, ih) = (image.width(), image.height() / 2);
+        let scaled_to_height = (iw * th / ih.max(1), th);
+        let scaled_to_width = (tw, ih * tw / iw.max(1));
+        let (w, h) = if scaled_to_height.0 <= tw {
+            scaled_to_height
+        } else {
+            scaled_to_width
+        };
+        (Some(w), Some(h))
+    } else {
+        (None, None)
+    };
+    viuer::print(
+        image,
+        &viuer::Config {
+            width,
+            height,
+            absolute_offset: false,
+            transparent: true,
+            use_kitty: false,
+            use_iterm: false,
+            ..Default::default()
+        },
+    )
+    .map(|(_, rows)| rows)
+    .map_err(|e| format!("Failed to show image: {e}"))
+}
+
+fn write_stdout(s: &str) -> Result<(), String> {
+    let mut stdout = stdout().lock();
+    (stdout.write_all(s.as_bytes()))
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("Failed to show image: {e}"))
+}
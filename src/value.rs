@@ -1666,6 +1666,10 @@ impl ValueBuilder {
 enum MapNumRep {
     #[serde(rename = "NaN")]
     NaN,
+    #[serde(rename = "∞")]
+    Infinity,
+    #[serde(rename = "-∞")]
+    NegInfinity,
     #[serde(rename = "empty")]
     MapEmpty,
     #[serde(rename = "tomb")]
@@ -1684,6 +1688,10 @@ impl From<f64> for MapNumRep {
             } else {
                 Self::NaN
             }
+        } else if n == f64::INFINITY {
+            Self::Infinity
+        } else if n == f64::NEG_INFINITY {
+            Self::NegInfinity
         } else {
             Self::Num(n)
         }
@@ -1694,6 +1702,8 @@ impl From<MapNumRep> for f64 {
     fn from(rep: MapNumRep) -> Self {
         match rep {
             MapNumRep::NaN => f64::NAN,
+            MapNumRep::Infinity => f64::INFINITY,
+            MapNumRep::NegInfinity => f64::NEG_INFINITY,
             MapNumRep::MapEmpty => EMPTY_NAN,
             MapNumRep::MapTombstone => TOMBSTONE_NAN,
             MapNumRep::Num(n) => n,